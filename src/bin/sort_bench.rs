@@ -0,0 +1,319 @@
+//! Benchmark runner for the sorting algorithms, std only.
+//!
+//! ```text
+//! cargo run --release --bin sort_bench -- [options]
+//!
+//!   --sizes 1000,10000,100000     input lengths
+//!   --distributions random,sorted distributions, see `Distribution::name`
+//!   --algorithms merge,quick      algorithms, all by default
+//!   --runs 11                     timed runs per measurement
+//!   --quadratic-limit 10000       skip O(n^2) sorts above this length
+//!   --csv                         print CSV instead of a table
+//! ```
+
+use std::env;
+use std::hint::black_box;
+use std::process;
+use std::time::{Duration, Instant};
+
+use algorithm::sorting::bench::Distribution;
+use algorithm::sorting::{
+    bottom_up_merge_sort, bubble_sort, heap_sort, insertion_sort, merge_sort, par_merge_sort,
+    quick_sort, radix_sort, shell_sort, tim_sort,
+};
+
+struct Algorithm {
+    name: &'static str,
+    quadratic: bool,
+    sort: fn(&mut [u64]),
+}
+
+const ALGORITHMS: [Algorithm; 11] = [
+    Algorithm {
+        name: "bubble",
+        quadratic: true,
+        sort: bubble_sort,
+    },
+    Algorithm {
+        name: "insertion",
+        quadratic: true,
+        sort: insertion_sort,
+    },
+    Algorithm {
+        name: "shell",
+        quadratic: false,
+        sort: shell_sort,
+    },
+    Algorithm {
+        name: "heap",
+        quadratic: false,
+        sort: heap_sort,
+    },
+    Algorithm {
+        name: "merge",
+        quadratic: false,
+        sort: merge_sort,
+    },
+    Algorithm {
+        name: "bottom-up-merge",
+        quadratic: false,
+        sort: bottom_up_merge_sort,
+    },
+    Algorithm {
+        name: "parallel-merge",
+        quadratic: false,
+        sort: par_merge_sort,
+    },
+    Algorithm {
+        name: "tim",
+        quadratic: false,
+        sort: tim_sort,
+    },
+    Algorithm {
+        name: "quick",
+        quadratic: false,
+        sort: quick_sort,
+    },
+    Algorithm {
+        name: "radix",
+        quadratic: false,
+        sort: radix_sort,
+    },
+    Algorithm {
+        name: "std-stable",
+        quadratic: false,
+        sort: <[u64]>::sort,
+    },
+];
+
+struct Options {
+    sizes: Vec<usize>,
+    distributions: Vec<Distribution>,
+    algorithms: Vec<&'static Algorithm>,
+    runs: usize,
+    quadratic_limit: usize,
+    csv: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            sizes: vec![1_000, 10_000, 100_000],
+            distributions: vec![
+                Distribution::Random,
+                Distribution::Sorted,
+                Distribution::Reversed,
+                Distribution::FewUnique,
+                Distribution::OrganPipe,
+            ],
+            algorithms: ALGORITHMS.iter().collect(),
+            runs: 11,
+            quadratic_limit: 10_000,
+            csv: false,
+        }
+    }
+}
+
+struct Measurement {
+    algorithm: &'static str,
+    distribution: Distribution,
+    size: usize,
+    runs: usize,
+    min: Duration,
+    p10: Duration,
+    median: Duration,
+    p90: Duration,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("sort_bench: {}", message);
+            process::exit(2);
+        }
+    };
+
+    if options.csv {
+        println!("algorithm,distribution,size,runs,min_ns,p10_ns,median_ns,p90_ns");
+    } else {
+        println!(
+            "{:<16} {:<11} {:>9} {:>12} {:>12} {:>12} {:>12}",
+            "algorithm", "distribution", "size", "min", "p10", "median", "p90"
+        );
+    }
+
+    for &size in &options.sizes {
+        for &distribution in &options.distributions {
+            let input = distribution.generate(size, 42);
+            for algorithm in &options.algorithms {
+                if algorithm.quadratic && size > options.quadratic_limit {
+                    continue;
+                }
+
+                let m = measure(algorithm, distribution, &input, options.runs);
+                if options.csv {
+                    println!(
+                        "{},{},{},{},{},{},{},{}",
+                        m.algorithm,
+                        m.distribution.name(),
+                        m.size,
+                        m.runs,
+                        m.min.as_nanos(),
+                        m.p10.as_nanos(),
+                        m.median.as_nanos(),
+                        m.p90.as_nanos()
+                    );
+                } else {
+                    println!(
+                        "{:<16} {:<11} {:>9} {:>12?} {:>12?} {:>12?} {:>12?}",
+                        m.algorithm,
+                        m.distribution.name(),
+                        m.size,
+                        m.min,
+                        m.p10,
+                        m.median,
+                        m.p90
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn measure(
+    algorithm: &Algorithm,
+    distribution: Distribution,
+    input: &[u64],
+    runs: usize,
+) -> Measurement {
+    let expected_len = input.len();
+    let mut times = Vec::with_capacity(runs);
+
+    // one untimed warm up run, also checks the result
+    let mut arr = input.to_vec();
+    (algorithm.sort)(&mut arr);
+    assert!(
+        arr.len() == expected_len && arr.windows(2).all(|w| w[0] <= w[1]),
+        "{} did not sort",
+        algorithm.name
+    );
+
+    for _ in 0..runs {
+        let mut arr = input.to_vec();
+        let start = Instant::now();
+        (algorithm.sort)(black_box(&mut arr));
+        times.push(start.elapsed());
+        black_box(&arr);
+    }
+    times.sort();
+
+    Measurement {
+        algorithm: algorithm.name,
+        distribution,
+        size: input.len(),
+        runs,
+        min: times[0],
+        p10: percentile(&times, 10),
+        median: percentile(&times, 50),
+        p90: percentile(&times, 90),
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `times`.
+fn percentile(times: &[Duration], p: usize) -> Duration {
+    let rank = (p * times.len()).div_ceil(100).max(1);
+    times[rank - 1]
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--sizes" => options.sizes = parse_list(&value()?, parse_number)?,
+            "--distributions" => options.distributions = parse_list(&value()?, parse_distribution)?,
+            "--algorithms" => options.algorithms = parse_list(&value()?, parse_algorithm)?,
+            "--runs" => options.runs = parse_number(&value()?)?.max(1),
+            "--quadratic-limit" => options.quadratic_limit = parse_number(&value()?)?,
+            "--csv" => options.csv = true,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_list<T>(value: &str, parse: fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    value.split(',').map(|s| parse(s.trim())).collect()
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .replace('_', "")
+        .parse()
+        .map_err(|_| format!("not a number: {}", value))
+}
+
+fn parse_distribution(value: &str) -> Result<Distribution, String> {
+    Distribution::ALL
+        .into_iter()
+        .find(|d| d.name() == value)
+        .ok_or(format!("unknown distribution: {}", value))
+}
+
+fn parse_algorithm(value: &str) -> Result<&'static Algorithm, String> {
+    ALGORITHMS
+        .iter()
+        .find(|a| a.name == value)
+        .ok_or(format!("unknown algorithm: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> impl Iterator<Item = String> + '_ {
+        s.split_whitespace().map(String::from)
+    }
+
+    #[test]
+    fn test_percentile() {
+        let times: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&times, 50), Duration::from_millis(5));
+        assert_eq!(percentile(&times, 90), Duration::from_millis(9));
+        assert_eq!(percentile(&times, 10), Duration::from_millis(1));
+        assert_eq!(percentile(&times[..1], 90), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(args(
+            "--sizes 10,1_000 --distributions sorted,organ-pipe --algorithms tim --runs 3 --csv",
+        ))
+        .unwrap();
+        assert_eq!(options.sizes, vec![10, 1000]);
+        assert_eq!(
+            options.distributions,
+            vec![Distribution::Sorted, Distribution::OrganPipe]
+        );
+        assert_eq!(options.algorithms.len(), 1);
+        assert_eq!(options.algorithms[0].name, "tim");
+        assert_eq!(options.runs, 3);
+        assert!(options.csv);
+
+        assert!(parse_args(args("--sizes")).is_err());
+        assert!(parse_args(args("--algorithms nope")).is_err());
+        assert!(parse_args(args("--bogus")).is_err());
+    }
+
+    #[test]
+    fn every_algorithm_sorts() {
+        for algorithm in &ALGORITHMS {
+            let input = Distribution::Random.generate(100, 1);
+            let m = measure(algorithm, Distribution::Random, &input, 3);
+            assert!(m.min <= m.median && m.median <= m.p90);
+        }
+    }
+}
@@ -1,105 +1,346 @@
-pub struct Heap {
-    items: Vec<i32>,
-    comparator: fn(i32, i32) -> bool,
+use std::ops::{Deref, DerefMut};
+use std::{slice, vec};
+
+/// d-ary heap over `Vec<T>`, binary unless `with_arity` says otherwise.
+///
+/// `comparator(a, b)` returns true when `a` belongs above `b`, so
+/// `|a, b| a > b` gives a max heap. The comparator may be any closure,
+/// including one that captures state or a `Box<dyn FnMut>`.
+pub struct Heap<T, F = fn(&T, &T) -> bool> {
+    items: Vec<T>,
+    comparator: F,
+    // children per node
+    arity: usize,
 }
 
-impl Heap {
-    pub fn new(comparator: fn(i32, i32) -> bool) -> Self {
-        Self {
-            items: vec![],
-            comparator,
-        }
-    }
+impl<T: Ord> Heap<T> {
     pub fn new_max() -> Self {
         Self::new(|a, b| a > b)
     }
     pub fn new_min() -> Self {
         Self::new(|a, b| a < b)
     }
+}
+
+impl<T, F> Heap<T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    pub fn new(comparator: F) -> Self {
+        Self::from_vec(vec![], comparator)
+    }
+
+    pub fn with_capacity(capacity: usize, comparator: F) -> Self {
+        Self::from_vec(Vec::with_capacity(capacity), comparator)
+    }
+
+    /// Builds the heap bottom-up in O(n).
+    pub fn from_vec(items: Vec<T>, comparator: F) -> Self {
+        let mut heap = Self {
+            items,
+            comparator,
+            arity: 2,
+        };
+        heap.heapify();
+        heap
+    }
+
+    /// Switches to `arity` children per node, O(n).
+    ///
+    /// A wider heap is shallower, so `add` is cheaper and the children of a
+    /// node share cache lines, while `pop` compares more children per level.
+    pub fn with_arity(mut self, arity: usize) -> Self {
+        assert!(arity >= 2, "a heap needs at least 2 children per node");
+        self.arity = arity;
+        self.heapify();
+        self
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    /// Mutable access to the top item. The heap is fixed up when the
+    /// returned guard is dropped, if the item was changed.
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, F>> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(PeekMut {
+            heap: self,
+            changed: false,
+        })
+    }
 
-    pub fn pop(&mut self) -> Option<i32> {
+    pub fn pop(&mut self) -> Option<T> {
         if self.is_empty() {
             return None;
         }
 
-        let next = Some(self.items.swap_remove(0));
+        let next = self.items.swap_remove(0);
+        self.sift_down(0);
 
-        if !self.is_empty() {
-            // heapify down
-            let mut idx = 0;
-            while self.children_present(idx) {
-                let ldx = self.left_child_idx(idx);
-                let rdx = self.right_child_idx(idx);
+        Some(next)
+    }
+
+    pub fn add(&mut self, val: T) {
+        self.items.push(val);
+        self.sift_up(self.len() - 1);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
 
-                let cdx = if rdx >= self.len() {
-                    ldx
-                } else {
-                    if (self.comparator)(self.items[ldx], self.items[rdx]) {
-                        ldx
-                    } else {
-                        rdx
-                    }
-                };
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// All items, the top one last. A max heap gives ascending order, like
+    /// `std::collections::BinaryHeap`.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        for end in (1..self.len()).rev() {
+            self.items.swap(0, end);
+            self.sift_down_to(0, end);
+        }
+        self.items
+    }
+
+    /// Removes the items in pop order. Whatever isn't consumed is removed
+    /// when the iterator is dropped.
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T, F> {
+        DrainSorted { heap: self }
+    }
+
+    // whether items[a] belongs above items[b]
+    fn above(&mut self, a: usize, b: usize) -> bool {
+        (self.comparator)(&self.items[a], &self.items[b])
+    }
+
+    // bottom-up from the last parent
+    fn heapify(&mut self) {
+        if let Some(last) = self.len().checked_sub(1) {
+            let parent = self.parent_idx(last).unwrap_or(0);
+            for idx in (0..=parent).rev() {
+                self.sift_down(idx);
+            }
+        }
+    }
 
-                if !(self.comparator)(self.items[idx], self.items[cdx]) {
-                    self.items.swap(idx, cdx);
+    // heapify down
+    fn sift_down(&mut self, idx: usize) {
+        self.sift_down_to(idx, self.len());
+    }
+
+    // heapify down within items[..end]
+    fn sift_down_to(&mut self, mut idx: usize, end: usize) {
+        while self.left_child_idx(idx) < end {
+            let ldx = self.left_child_idx(idx);
+            let rdx = self.right_child_idx(idx).min(end - 1);
+
+            // the child that belongs highest
+            let mut cdx = ldx;
+            for sdx in ldx + 1..=rdx {
+                if self.above(sdx, cdx) {
+                    cdx = sdx;
                 }
-                idx = cdx;
             }
+
+            if !self.above(cdx, idx) {
+                break;
+            }
+            self.items.swap(idx, cdx);
+            idx = cdx;
         }
+    }
 
-        next
+    // heapify up
+    fn sift_up(&mut self, mut idx: usize) {
+        while let Some(pdx) = self.parent_idx(idx) {
+            if !self.above(idx, pdx) {
+                break;
+            }
+            self.items.swap(idx, pdx);
+            idx = pdx;
+        }
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        self.items.len() == 0
+impl<T, F> Heap<T, F> {
+    /// All items, in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Iterates the items in no particular order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Removes all items, in no particular order.
+    pub fn drain(&mut self) -> vec::Drain<'_, T> {
+        self.items.drain(..)
     }
 
-    // for heapify down
     fn left_child_idx(&self, idx: usize) -> usize {
-        2 * idx + 1
+        left_child_idx(idx, self.arity)
     }
     fn right_child_idx(&self, idx: usize) -> usize {
-        self.left_child_idx(idx) + 1
+        right_child_idx(idx, self.arity)
     }
-    fn children_present(&self, idx: usize) -> bool {
-        self.left_child_idx(idx) < self.len()
+
+    fn parent_idx(&self, idx: usize) -> Option<usize> {
+        parent_idx(idx, self.arity)
     }
+}
 
-    pub fn add(&mut self, val: i32) {
-        self.items.push(val);
+// in a heap with `arity` children per node, the children of `idx` are
+// left_child_idx(idx)..=right_child_idx(idx)
+pub(crate) fn left_child_idx(idx: usize, arity: usize) -> usize {
+    arity * idx + 1
+}
+pub(crate) fn right_child_idx(idx: usize, arity: usize) -> usize {
+    left_child_idx(idx, arity) + arity - 1
+}
 
-        // heapify up
-        let mut idx = self.len() - 1;
-        while let Some(pdx) = self.parent_idx(idx) {
-            if (self.comparator)(self.items[idx], self.items[pdx]) {
-                self.items.swap(idx, pdx);
-            }
+pub(crate) fn parent_idx(idx: usize, arity: usize) -> Option<usize> {
+    if idx > 0 {
+        Some((idx - 1) / arity)
+    } else {
+        None
+    }
+}
 
-            idx = pdx;
+/// Collects into a max heap, like `std::collections::BinaryHeap`.
+impl<T: Ord> FromIterator<T> for Heap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_vec(iter.into_iter().collect(), |a, b| a > b)
+    }
+}
+
+impl<T, F> Extend<T> for Heap<T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.add(val);
         }
     }
+}
 
-    fn parent_idx(&self, idx: usize) -> Option<usize> {
-        if idx > 0 {
-            Some((idx - 1) / 2)
-        } else {
-            None
+/// Guard returned by `Heap::peek_mut`.
+pub struct PeekMut<'a, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    heap: &'a mut Heap<T, F>,
+    changed: bool,
+}
+
+impl<T, F> PeekMut<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    /// Removes the peeked item from the heap.
+    pub fn pop(mut this: Self) -> T {
+        this.changed = false;
+        this.heap.pop().unwrap()
+    }
+}
+
+impl<T, F> Deref for PeekMut<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.heap.items[0]
+    }
+}
+
+impl<T, F> DerefMut for PeekMut<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed = true;
+        &mut self.heap.items[0]
+    }
+}
+
+impl<T, F> Drop for PeekMut<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    fn drop(&mut self) {
+        if self.changed {
+            self.heap.sift_down(0);
         }
     }
+}
+
+/// Iterator returned by `Heap::drain_sorted`.
+pub struct DrainSorted<'a, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    heap: &'a mut Heap<T, F>,
+}
+
+impl<T, F> Iterator for DrainSorted<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.heap.pop()
+    }
 
-    pub fn len(&self) -> usize {
-        self.items.len()
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T, F> Drop for DrainSorted<'_, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    fn drop(&mut self) {
+        self.heap.clear();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sorting::Rng;
+    use std::cell::Cell;
+
+    // every test runs on each of these layouts
+    const ARITIES: [usize; 4] = [2, 3, 4, 8];
+
+    // no item is above its parent
+    fn check<T, F: FnMut(&T, &T) -> bool>(heap: &mut Heap<T, F>) {
+        for idx in 1..heap.len() {
+            let pdx = heap.parent_idx(idx).unwrap();
+            assert!(!heap.above(idx, pdx));
+        }
+    }
 
     #[test]
     fn test_parent_idx() {
-        let heap = Heap::new_max();
+        let heap: Heap<i32> = Heap::new_max();
         assert_eq!(None, heap.parent_idx(0));
         assert_eq!(Some(0), heap.parent_idx(1));
         assert_eq!(Some(0), heap.parent_idx(2));
@@ -107,48 +348,274 @@ mod tests {
         assert_eq!(Some(1), heap.parent_idx(4));
         assert_eq!(Some(2), heap.parent_idx(5));
         assert_eq!(Some(2), heap.parent_idx(6));
+
+        for d in ARITIES {
+            let heap: Heap<i32> = Heap::new_max().with_arity(d);
+            assert_eq!(d, heap.arity());
+            assert_eq!(None, heap.parent_idx(0));
+            for idx in 0..100 {
+                let ldx = heap.left_child_idx(idx);
+                let rdx = heap.right_child_idx(idx);
+                assert_eq!(d, rdx - ldx + 1);
+                for cdx in ldx..=rdx {
+                    assert_eq!(Some(idx), heap.parent_idx(cdx));
+                }
+            }
+        }
     }
 
     #[test]
     fn test_empy_hep() {
-        let mut heap = Heap::new_max();
-        assert_eq!(None, heap.pop());
+        for d in ARITIES {
+            let mut heap: Heap<i32> = Heap::new_max().with_arity(d);
+            assert_eq!(None, heap.pop());
+        }
     }
 
     #[test]
     fn test_max_heap() {
-        let mut heap = Heap::new_max();
-        heap.add(3);
-        heap.add(9);
-        heap.add(2);
-        heap.add(1);
-        heap.add(4);
-        heap.add(5);
-        assert_eq!(6, heap.len());
-        assert_eq!(Some(9), heap.pop());
-        assert_eq!(Some(5), heap.pop());
-        assert_eq!(Some(4), heap.pop());
-        assert_eq!(Some(3), heap.pop());
-        assert_eq!(Some(2), heap.pop());
-        assert_eq!(Some(1), heap.pop());
-        assert_eq!(None, heap.pop());
+        for d in ARITIES {
+            let mut heap = Heap::new_max().with_arity(d);
+            heap.add(3);
+            heap.add(9);
+            heap.add(2);
+            heap.add(1);
+            heap.add(4);
+            heap.add(5);
+            assert_eq!(6, heap.len());
+            assert_eq!(Some(9), heap.pop());
+            assert_eq!(Some(5), heap.pop());
+            assert_eq!(Some(4), heap.pop());
+            assert_eq!(Some(3), heap.pop());
+            assert_eq!(Some(2), heap.pop());
+            assert_eq!(Some(1), heap.pop());
+            assert_eq!(None, heap.pop());
+        }
     }
+
     #[test]
     fn test_min_heap() {
-        let mut heap = Heap::new_min();
-        heap.add(3);
-        heap.add(9);
-        heap.add(2);
-        heap.add(1);
-        heap.add(4);
-        heap.add(5);
-        assert_eq!(6, heap.len());
-        assert_eq!(Some(1), heap.pop());
-        assert_eq!(Some(2), heap.pop());
-        assert_eq!(Some(3), heap.pop());
-        assert_eq!(Some(4), heap.pop());
-        assert_eq!(Some(5), heap.pop());
-        assert_eq!(Some(9), heap.pop());
+        for d in ARITIES {
+            let mut heap = Heap::new_min().with_arity(d);
+            heap.add(3);
+            heap.add(9);
+            heap.add(2);
+            heap.add(1);
+            heap.add(4);
+            heap.add(5);
+            assert_eq!(6, heap.len());
+            assert_eq!(Some(1), heap.pop());
+            assert_eq!(Some(2), heap.pop());
+            assert_eq!(Some(3), heap.pop());
+            assert_eq!(Some(4), heap.pop());
+            assert_eq!(Some(5), heap.pop());
+            assert_eq!(Some(9), heap.pop());
+            assert_eq!(None, heap.pop());
+        }
+    }
+
+    #[test]
+    fn test_tuples() {
+        for d in ARITIES {
+            let mut heap = Heap::new_min().with_arity(d);
+            heap.add((2, "write"));
+            heap.add((1, "read"));
+            heap.add((3, "sync"));
+            assert_eq!(Some((1, "read")), heap.pop());
+            assert_eq!(Some((2, "write")), heap.pop());
+            assert_eq!(Some((3, "sync")), heap.pop());
+        }
+    }
+
+    #[test]
+    fn test_strings() {
+        for d in ARITIES {
+            let mut heap = Heap::new_max().with_arity(d);
+            for s in ["pear", "apple", "fig"] {
+                heap.add(s.to_string());
+            }
+            assert_eq!(Some("pear".to_string()), heap.pop());
+            assert_eq!(Some("fig".to_string()), heap.pop());
+            assert_eq!(Some("apple".to_string()), heap.pop());
+        }
+    }
+
+    #[test]
+    fn test_capturing_comparator() {
+        let priority = [5, 1, 4, 2];
+        for d in ARITIES {
+            let mut comparisons = 0;
+            let mut heap = Heap::new(|a: &usize, b: &usize| {
+                comparisons += 1;
+                priority[*a] > priority[*b]
+            })
+            .with_arity(d);
+            for job in 0..priority.len() {
+                heap.add(job);
+            }
+            let order: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+            assert_eq!(vec![0, 2, 3, 1], order);
+            assert!(comparisons > 0);
+        }
+    }
+
+    type BoxedComparator = Box<dyn FnMut(&i32, &i32) -> bool>;
+
+    #[test]
+    fn test_boxed_comparator() {
+        let reverse = true;
+        for d in ARITIES {
+            let comparator: BoxedComparator = if reverse {
+                Box::new(|a, b| a < b)
+            } else {
+                Box::new(|a, b| a > b)
+            };
+            let mut heap = Heap::new(comparator).with_arity(d);
+            heap.add(2);
+            heap.add(1);
+            heap.add(3);
+            assert_eq!(Some(1), heap.pop());
+        }
+    }
+
+    #[test]
+    fn test_many() {
+        for d in ARITIES {
+            let mut heap = Heap::new_min().with_arity(d);
+            let mut rng = Rng::new(1);
+            let mut items = vec![];
+            for _ in 0..1000 {
+                let x = rng.below(1 << 24);
+                items.push(x);
+                heap.add(x);
+            }
+            check(&mut heap);
+            items.sort();
+            let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+            assert_eq!(items, popped);
+        }
+    }
+
+    #[test]
+    fn test_with_arity_rebuilds() {
+        let mut heap: Heap<i32> = (0..100).collect();
+        for d in [3, 8, 2, 4] {
+            heap = heap.with_arity(d);
+            check(&mut heap);
+        }
+        assert_eq!((0..100).collect::<Vec<_>>(), heap.into_sorted_vec());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unary_heap() {
+        let _: Heap<i32> = Heap::new_max().with_arity(1);
+    }
+
+    #[test]
+    fn test_peek() {
+        for d in ARITIES {
+            let mut heap = Heap::new_min().with_arity(d);
+            assert_eq!(None, heap.peek());
+            assert!(heap.peek_mut().is_none());
+
+            heap.extend([5, 3, 8, 1]);
+            assert_eq!(Some(&1), heap.peek());
+            assert_eq!(4, heap.len());
+        }
+    }
+
+    #[test]
+    fn test_peek_mut() {
+        for d in ARITIES {
+            let mut heap: Heap<i32> = [5, 3, 8, 1].into_iter().collect();
+            heap = heap.with_arity(d);
+            assert_eq!(Some(&8), heap.peek());
+
+            // lowering the top moves it down once the guard is dropped
+            *heap.peek_mut().unwrap() = 2;
+            assert_eq!(Some(&5), heap.peek());
+
+            {
+                let mut top = heap.peek_mut().unwrap();
+                *top += 10;
+            }
+            assert_eq!(Some(&15), heap.peek());
+
+            let top = heap.peek_mut().unwrap();
+            assert_eq!(15, PeekMut::pop(top));
+            assert_eq!(vec![1, 2, 3], heap.into_sorted_vec());
+        }
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let items: Vec<u64> = (0..1000).map(|n| n * 7919 % 1000).collect();
+        let comparisons = Cell::new(0);
+        let mut heap = Heap::from_vec(items, |a, b| {
+            comparisons.set(comparisons.get() + 1);
+            a < b
+        });
+        assert!(comparisons.get() <= 2 * 1000);
+
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!((0..1000).collect::<Vec<_>>(), popped);
+    }
+
+    #[test]
+    fn test_into_sorted_vec() {
+        for d in ARITIES {
+            let max: Heap<i32> = vec![3, 1, 4, 1, 5, 9, 2, 6].into_iter().collect();
+            let max = max.with_arity(d);
+            assert_eq!(vec![1, 1, 2, 3, 4, 5, 6, 9], max.into_sorted_vec());
+
+            let mut min = Heap::new_min().with_arity(d);
+            min.extend(vec![3, 1, 4, 1, 5]);
+            assert_eq!(vec![5, 4, 3, 1, 1], min.into_sorted_vec());
+
+            let empty: Heap<i32> = Heap::new_max().with_arity(d);
+            assert!(empty.into_sorted_vec().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_iter_and_into_vec() {
+        let heap: Heap<i32> = (1..=5).collect();
+        let mut items: Vec<_> = heap.iter().copied().collect();
+        items.sort();
+        assert_eq!(vec![1, 2, 3, 4, 5], items);
+
+        let mut items = heap.into_vec();
+        items.sort();
+        assert_eq!(vec![1, 2, 3, 4, 5], items);
+    }
+
+    #[test]
+    fn test_drain() {
+        for d in ARITIES {
+            let mut heap: Heap<i32> = (1..=5).collect();
+            heap = heap.with_arity(d);
+            let mut drained: Vec<_> = heap.drain().collect();
+            drained.sort();
+            assert_eq!(vec![1, 2, 3, 4, 5], drained);
+            assert!(heap.is_empty());
+
+            heap.extend(1..=5);
+            assert_eq!(vec![5, 4, 3, 2, 1], heap.drain_sorted().collect::<Vec<_>>());
+
+            heap.extend(1..=5);
+            assert_eq!(vec![5, 4], heap.drain_sorted().take(2).collect::<Vec<_>>());
+            assert!(heap.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_clear_and_capacity() {
+        let mut heap = Heap::with_capacity(16, |a: &i32, b: &i32| a > b);
+        assert!(heap.items.capacity() >= 16);
+        heap.extend([1, 2, 3]);
+        heap.clear();
+        assert!(heap.is_empty());
         assert_eq!(None, heap.pop());
     }
 }
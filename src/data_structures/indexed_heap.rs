@@ -0,0 +1,485 @@
+use super::heap::{left_child_idx, parent_idx, right_child_idx};
+
+/// Handle of an item in an `IndexedHeap`, returned by `push`.
+///
+/// A handle stays valid until its item is popped or removed, and is never
+/// reused for another item afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Handle {
+    slot: usize,
+    generation: u64,
+}
+
+struct Node<T, P> {
+    item: T,
+    priority: P,
+    // index into `IndexedHeap::heap`
+    pos: usize,
+}
+
+struct Slot<T, P> {
+    generation: u64,
+    node: Option<Node<T, P>>,
+}
+
+/// d-ary heap of `(item, priority)` pairs that can change or remove any
+/// item through the `Handle` returned by `push`, in O(log n). Binary unless
+/// `with_arity` says otherwise.
+///
+/// The heap itself only holds slot indices, each slot knows its position in
+/// the heap, and every swap during a sift updates both.
+///
+/// `comparator(a, b)` returns true when priority `a` belongs above `b`, the
+/// same as for `Heap`.
+pub struct IndexedHeap<T, P, F = fn(&P, &P) -> bool> {
+    heap: Vec<usize>,
+    slots: Vec<Slot<T, P>>,
+    free: Vec<usize>,
+    comparator: F,
+    arity: usize,
+}
+
+impl<T, P: Ord> IndexedHeap<T, P> {
+    pub fn new_max() -> Self {
+        Self::new(|a, b| a > b)
+    }
+    pub fn new_min() -> Self {
+        Self::new(|a, b| a < b)
+    }
+}
+
+impl<T, P, F> IndexedHeap<T, P, F>
+where
+    F: FnMut(&P, &P) -> bool,
+{
+    pub fn new(comparator: F) -> Self {
+        Self {
+            heap: vec![],
+            slots: vec![],
+            free: vec![],
+            comparator,
+            arity: 2,
+        }
+    }
+
+    /// Switches to `arity` children per node, O(n), like `Heap::with_arity`.
+    pub fn with_arity(mut self, arity: usize) -> Self {
+        assert!(arity >= 2, "a heap needs at least 2 children per node");
+        self.arity = arity;
+        for idx in (0..self.heap.len()).rev() {
+            self.sift_down(idx);
+        }
+        self
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn push(&mut self, item: T, priority: P) -> Handle {
+        let pos = self.heap.len();
+        let node = Some(Node {
+            item,
+            priority,
+            pos,
+        });
+
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot].node = node;
+                slot
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node,
+                });
+                self.slots.len() - 1
+            }
+        };
+
+        self.heap.push(slot);
+        self.sift_up(pos);
+        Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        }
+    }
+
+    pub fn peek(&self) -> Option<(&T, &P)> {
+        let node = self.node(*self.heap.first()?);
+        Some((&node.item, &node.priority))
+    }
+
+    pub fn pop(&mut self) -> Option<(T, P)> {
+        let slot = *self.heap.first()?;
+        Some(self.remove_at(slot))
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.slots
+            .get(handle.slot)
+            .is_some_and(|slot| slot.generation == handle.generation && slot.node.is_some())
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<(&T, &P)> {
+        if !self.contains(handle) {
+            return None;
+        }
+        let node = self.node(handle.slot);
+        Some((&node.item, &node.priority))
+    }
+
+    /// Sets the priority of `handle` and moves it up or down to match.
+    /// Returns the old priority, `None` if the handle isn't in the heap.
+    pub fn change_priority(&mut self, handle: Handle, priority: P) -> Option<P> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        let node = self.node_mut(handle.slot);
+        let old = std::mem::replace(&mut node.priority, priority);
+        let pos = node.pos;
+        let pos = self.sift_up(pos);
+        self.sift_down(pos);
+        Some(old)
+    }
+
+    /// Moves `handle` towards the top: smaller priority in a min heap, larger
+    /// in a max heap. Returns false, without changing anything, if the handle
+    /// isn't in the heap or `priority` belongs below the current one.
+    pub fn decrease_key(&mut self, handle: Handle, priority: P) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+
+        let current = &self.slots[handle.slot].node.as_ref().unwrap().priority;
+        if (self.comparator)(current, &priority) {
+            return false;
+        }
+
+        let node = self.node_mut(handle.slot);
+        node.priority = priority;
+        let pos = node.pos;
+        self.sift_up(pos);
+        true
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<(T, P)> {
+        if !self.contains(handle) {
+            return None;
+        }
+        Some(self.remove_at(handle.slot))
+    }
+
+    fn remove_at(&mut self, slot: usize) -> (T, P) {
+        let pos = self.node(slot).pos;
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        self.heap.pop();
+
+        // the former last item now sits at `pos`, it may have to go either way
+        if pos < self.heap.len() {
+            let pos = self.sift_up(pos);
+            self.sift_down(pos);
+        }
+
+        let slot_ref = &mut self.slots[slot];
+        let node = slot_ref.node.take().unwrap();
+        // a slot whose generation runs out is retired instead of wrapping
+        if let Some(generation) = slot_ref.generation.checked_add(1) {
+            slot_ref.generation = generation;
+            self.free.push(slot);
+        }
+        (node.item, node.priority)
+    }
+
+    // whether heap[a] belongs above heap[b]
+    fn above(&mut self, a: usize, b: usize) -> bool {
+        let a = &self.slots[self.heap[a]].node.as_ref().unwrap().priority;
+        let b = &self.slots[self.heap[b]].node.as_ref().unwrap().priority;
+        (self.comparator)(a, b)
+    }
+
+    // heapify up, returns the final position
+    fn sift_up(&mut self, mut idx: usize) -> usize {
+        while let Some(pdx) = parent_idx(idx, self.arity) {
+            if !self.above(idx, pdx) {
+                break;
+            }
+            self.swap(idx, pdx);
+            idx = pdx;
+        }
+        idx
+    }
+
+    // heapify down
+    fn sift_down(&mut self, mut idx: usize) {
+        let end = self.heap.len();
+        while left_child_idx(idx, self.arity) < end {
+            let ldx = left_child_idx(idx, self.arity);
+            let rdx = right_child_idx(idx, self.arity).min(end - 1);
+
+            // the child that belongs highest
+            let mut cdx = ldx;
+            for sdx in ldx + 1..=rdx {
+                if self.above(sdx, cdx) {
+                    cdx = sdx;
+                }
+            }
+
+            if !self.above(cdx, idx) {
+                break;
+            }
+            self.swap(idx, cdx);
+            idx = cdx;
+        }
+    }
+}
+
+impl<T, P, F> IndexedHeap<T, P, F> {
+    // swaps two heap positions and keeps the position map in sync
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.node_mut(self.heap[a]).pos = a;
+        self.node_mut(self.heap[b]).pos = b;
+    }
+
+    fn node(&self, slot: usize) -> &Node<T, P> {
+        self.slots[slot].node.as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, slot: usize) -> &mut Node<T, P> {
+        self.slots[slot].node.as_mut().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Rng;
+
+    const ARITIES: [usize; 4] = [2, 3, 4, 8];
+
+    // every slot in the heap knows its position and no child is above its parent
+    fn check<T, P: Ord>(heap: &IndexedHeap<T, P>, min: bool) {
+        for (pos, &slot) in heap.heap.iter().enumerate() {
+            assert_eq!(pos, heap.node(slot).pos);
+            if let Some(pdx) = parent_idx(pos, heap.arity) {
+                let parent = &heap.node(heap.heap[pdx]).priority;
+                let child = &heap.node(slot).priority;
+                assert!(if min {
+                    parent <= child
+                } else {
+                    parent >= child
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut heap = IndexedHeap::new_min();
+        heap.push("c", 3);
+        heap.push("a", 1);
+        heap.push("b", 2);
+        assert_eq!(3, heap.len());
+        assert_eq!(Some((&"a", &1)), heap.peek());
+        assert_eq!(Some(("a", 1)), heap.pop());
+        assert_eq!(Some(("b", 2)), heap.pop());
+        assert_eq!(Some(("c", 3)), heap.pop());
+        assert_eq!(None, heap.pop());
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn test_change_priority() {
+        let mut heap = IndexedHeap::new_max();
+        let a = heap.push('a', 10);
+        let b = heap.push('b', 20);
+        let c = heap.push('c', 30);
+
+        assert_eq!(Some(10), heap.change_priority(a, 40));
+        assert_eq!(Some((&'a', &40)), heap.peek());
+        assert_eq!(Some(40), heap.change_priority(a, 0));
+        assert_eq!(Some(30), heap.change_priority(c, 5));
+        check(&heap, false);
+
+        assert_eq!(Some(('b', 20)), heap.pop());
+        assert_eq!(None, heap.change_priority(b, 1));
+        assert_eq!(Some(('c', 5)), heap.pop());
+        assert_eq!(Some(('a', 0)), heap.pop());
+    }
+
+    #[test]
+    fn test_decrease_key() {
+        let mut heap = IndexedHeap::new_min();
+        let a = heap.push('a', 10);
+        let b = heap.push('b', 20);
+
+        assert!(heap.decrease_key(b, 5));
+        assert_eq!(Some((&'b', &5)), heap.peek());
+        // moving down isn't a decrease
+        assert!(!heap.decrease_key(a, 15));
+        assert_eq!(Some((&'a', &10)), heap.get(a));
+        // equal is fine
+        assert!(heap.decrease_key(a, 10));
+    }
+
+    #[test]
+    fn test_remove_and_contains() {
+        let mut heap = IndexedHeap::new_min();
+        let handles: Vec<_> = (0..10).map(|n| heap.push(n, n)).collect();
+
+        assert_eq!(Some((4, 4)), heap.remove(handles[4]));
+        assert!(!heap.contains(handles[4]));
+        assert_eq!(None, heap.remove(handles[4]));
+        assert_eq!(None, heap.get(handles[4]));
+        check(&heap, true);
+
+        // the freed slot is reused, the old handle stays dead
+        let new = heap.push(42, -1);
+        assert_ne!(handles[4], new);
+        assert!(!heap.contains(handles[4]));
+        assert!(heap.contains(new));
+        assert_eq!(Some((42, -1)), heap.pop());
+        assert!(!heap.contains(new));
+
+        let rest: Vec<_> = std::iter::from_fn(|| heap.pop()).map(|(n, _)| n).collect();
+        assert_eq!(vec![0, 1, 2, 3, 5, 6, 7, 8, 9], rest);
+    }
+
+    #[test]
+    fn test_exhausted_slot_is_retired() {
+        let mut heap = IndexedHeap::new_min();
+        let a = heap.push('a', 1);
+        heap.slots[a.slot].generation = u64::MAX - 1;
+        let a = Handle {
+            slot: a.slot,
+            generation: u64::MAX - 1,
+        };
+        assert_eq!(Some(('a', 1)), heap.remove(a));
+
+        // the last generation is used once, then the slot is never handed out again
+        let b = heap.push('b', 2);
+        assert_eq!(a.slot, b.slot);
+        assert_eq!(Some(('b', 2)), heap.pop());
+        let c = heap.push('c', 3);
+        assert_ne!(b.slot, c.slot);
+        assert!(!heap.contains(a) && !heap.contains(b));
+        assert_eq!(Some(('c', 3)), heap.pop());
+    }
+
+    #[test]
+    fn test_random_operations() {
+        for d in ARITIES {
+            let mut heap = IndexedHeap::new_min().with_arity(d);
+            // (handle, priority) of everything that should be in the heap
+            let mut model: Vec<(Handle, u64)> = vec![];
+            let mut rng = Rng::new(7);
+            let mut random = |n: u64| rng.below(n);
+
+            for step in 0..5000 {
+                match random(5) {
+                    0 | 1 => {
+                        let p = random(1000);
+                        model.push((heap.push(step, p), p));
+                    }
+                    2 if !model.is_empty() => {
+                        let i = random(model.len() as u64) as usize;
+                        let p = random(1000);
+                        heap.change_priority(model[i].0, p);
+                        model[i].1 = p;
+                    }
+                    3 if !model.is_empty() => {
+                        let i = random(model.len() as u64) as usize;
+                        let (handle, p) = model.swap_remove(i);
+                        assert_eq!(Some(p), heap.remove(handle).map(|(_, p)| p));
+                    }
+                    _ => {
+                        let min = model.iter().map(|&(_, p)| p).min();
+                        let popped = heap.pop().map(|(_, p)| p);
+                        assert_eq!(min, popped);
+                        if let Some(p) = popped {
+                            let i = model.iter().position(|&(h, q)| q == p && !heap.contains(h));
+                            model.swap_remove(i.unwrap());
+                        }
+                    }
+                }
+                assert_eq!(model.len(), heap.len());
+            }
+            check(&heap, true);
+        }
+    }
+
+    #[test]
+    fn test_with_arity() {
+        let mut heap = IndexedHeap::new_min();
+        let handles: Vec<_> = (0..50).map(|n| heap.push(n, (n * 37) % 50)).collect();
+        let mut heap = heap.with_arity(4);
+        assert_eq!(4, heap.arity());
+        check(&heap, true);
+
+        assert_eq!(Some(11), heap.change_priority(handles[3], 100));
+        assert_eq!(Some((&0, &0)), heap.peek());
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).map(|(_, p)| p).collect();
+        assert_eq!(
+            (0..50)
+                .filter(|&p| p != 11)
+                .chain([100])
+                .collect::<Vec<_>>(),
+            popped
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_arity_of_one() {
+        IndexedHeap::<(), i32>::new_min().with_arity(1);
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        // (from, to, weight)
+        let edges = [
+            (0, 1, 4),
+            (0, 2, 1),
+            (2, 1, 2),
+            (1, 3, 1),
+            (2, 3, 5),
+            (3, 4, 3),
+        ];
+        let n = 5;
+        let mut dist = vec![u32::MAX; n];
+        let mut handles = vec![None; n];
+
+        let mut heap = IndexedHeap::new_min();
+        dist[0] = 0;
+        handles[0] = Some(heap.push(0, 0));
+
+        while let Some((node, d)) = heap.pop() {
+            for &(from, to, w) in &edges {
+                if from != node || d + w >= dist[to] {
+                    continue;
+                }
+                dist[to] = d + w;
+                match handles[to] {
+                    Some(handle) if heap.contains(handle) => {
+                        assert!(heap.decrease_key(handle, d + w));
+                    }
+                    _ => handles[to] = Some(heap.push(to, d + w)),
+                }
+            }
+        }
+
+        assert_eq!(vec![0, 3, 1, 4, 7], dist);
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 pub struct IntoIter<T>(List<T>);
 
 impl<T> Iterator for IntoIter<T> {
@@ -52,6 +54,82 @@ impl<'a, T> List<T> {
 
         ret
     }
+
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by_key<K, F>(&mut self, mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    /// Stable bottom-up merge sort. Only relinks the existing nodes, so it
+    /// neither allocates nor recurses.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut width = 1;
+        loop {
+            let mut rest = self.head.take();
+            let mut tail = &mut self.head;
+            let mut merges = 0;
+
+            // merge neighbouring runs of `width` nodes, appending to `tail`
+            while rest.is_some() {
+                let mut left = rest;
+                let mut right = split_after(&mut left, width);
+                rest = split_after(&mut right, width);
+                tail = merge(left, right, tail, &mut compare);
+                merges += 1;
+            }
+
+            if merges <= 1 {
+                return;
+            }
+            width *= 2;
+        }
+    }
+}
+
+// cuts `link` after `n` nodes and returns the rest
+fn split_after<T>(link: &mut Link<T>, n: usize) -> Link<T> {
+    let mut cur = link;
+    for _ in 0..n {
+        cur = &mut cur.as_mut()?.next;
+    }
+    cur.take()
+}
+
+// appends the merge of `a` and `b` to `tail`, returns the new end
+fn merge<'a, T, F>(
+    mut a: Link<T>,
+    mut b: Link<T>,
+    mut tail: &'a mut Link<T>,
+    compare: &mut F,
+) -> &'a mut Link<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    loop {
+        // on ties `a` goes first, which keeps the sort stable
+        let from = match (&a, &b) {
+            (Some(x), Some(y)) if compare(&y.elem, &x.elem) == Ordering::Less => &mut b,
+            (Some(_), _) => &mut a,
+            (None, Some(_)) => &mut b,
+            (None, None) => return tail,
+        };
+        let mut node = from.take().unwrap();
+        *from = node.next.take();
+        tail = &mut tail.insert(node).next;
+    }
 }
 
 struct Node<T> {
@@ -124,4 +202,61 @@ mod tests {
         assert_eq!(iter.next(), Some(1));
         assert_eq!(iter.next(), None);
     }
+
+    fn collect<T>(list: List<T>) -> Vec<T> {
+        list.into_iter().collect()
+    }
+
+    #[test]
+    fn sort() {
+        let mut list = List::new();
+        list.sort();
+        assert_eq!(None, list.peek());
+
+        for n in [4, 1, 7, 3, 9, 2, 8] {
+            list.push(n);
+        }
+        list.sort();
+        assert_eq!(collect(list), vec![1, 2, 3, 4, 7, 8, 9]);
+    }
+
+    #[test]
+    fn sort_by_descending() {
+        let mut list = List::new();
+        for n in 0..100 {
+            list.push(n * 37 % 101);
+        }
+        list.sort_by(|a, b| b.cmp(a));
+
+        let sorted = collect(list);
+        assert_eq!(sorted.len(), 100);
+        assert!(sorted.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn sort_by_key_is_stable() {
+        let mut list = List::new();
+        for i in (0..1000).rev() {
+            list.push((i % 7, i));
+        }
+        list.sort_by_key(|&(key, _)| key);
+
+        let sorted = collect(list);
+        let mut expected: Vec<_> = (0..1000).map(|i| (i % 7, i)).collect();
+        expected.sort_by_key(|&(key, _)| key);
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn sort_long_list() {
+        let mut list = List::new();
+        for n in 0..200_000u64 {
+            list.push(n.wrapping_mul(2654435761) % 1_000_003);
+        }
+        list.sort();
+
+        let sorted = collect(list);
+        assert_eq!(sorted.len(), 200_000);
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+    }
 }
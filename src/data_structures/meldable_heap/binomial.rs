@@ -0,0 +1,182 @@
+use std::cell::{Ref, RefCell};
+use std::mem;
+use std::ops::Deref;
+use std::rc::{Rc, Weak};
+
+use super::MeldableHeap;
+
+type Link<T> = Rc<RefCell<Node<T>>>;
+
+// where an item currently lives; items move between nodes in `decrease_key`
+type Locator<T> = RefCell<Weak<RefCell<Node<T>>>>;
+
+struct Node<T> {
+    item: T,
+    locator: Rc<Locator<T>>,
+    parent: Weak<RefCell<Node<T>>>,
+    // `children[k]` is a tree of rank k
+    children: Vec<Link<T>>,
+}
+
+/// Handle of an item in a `BinomialHeap`.
+pub struct Handle<T>(Weak<Locator<T>>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle(self.0.clone())
+    }
+}
+
+/// Binomial heap: at most one binomial tree of every rank, like the bits of
+/// `len`.
+///
+/// `push`, `pop`, `meld` and `decrease_key` are all O(log n). Adding a tree
+/// works like binary addition: two trees of rank k link into one of rank
+/// k + 1 and carry on.
+pub struct BinomialHeap<T> {
+    // `trees[k]` has rank k
+    trees: Vec<Option<Link<T>>>,
+    len: usize,
+}
+
+impl<T: Ord> BinomialHeap<T> {
+    pub fn new() -> Self {
+        Self {
+            trees: vec![],
+            len: 0,
+        }
+    }
+
+    fn add_tree(&mut self, mut tree: Link<T>) {
+        let mut rank = tree.borrow().children.len();
+        loop {
+            if rank == self.trees.len() {
+                self.trees.push(None);
+            }
+            match self.trees[rank].take() {
+                Some(other) => {
+                    tree = link(tree, other);
+                    rank += 1;
+                }
+                None => {
+                    self.trees[rank] = Some(tree);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn min_rank(&self) -> Option<usize> {
+        self.trees
+            .iter()
+            .enumerate()
+            .filter_map(|(rank, tree)| Some((rank, tree.as_ref()?)))
+            .min_by(|(_, a), (_, b)| a.borrow().item.cmp(&b.borrow().item))
+            .map(|(rank, _)| rank)
+    }
+}
+
+impl<T: Ord> Default for BinomialHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> MeldableHeap<T> for BinomialHeap<T> {
+    type Handle = Handle<T>;
+
+    fn push(&mut self, item: T) -> Handle<T> {
+        let locator = Rc::new(RefCell::new(Weak::new()));
+        let handle = Handle(Rc::downgrade(&locator));
+        let node = Rc::new(RefCell::new(Node {
+            item,
+            locator,
+            parent: Weak::new(),
+            children: vec![],
+        }));
+        *node.borrow().locator.borrow_mut() = Rc::downgrade(&node);
+
+        self.add_tree(node);
+        self.len += 1;
+        handle
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let rank = self.min_rank()?;
+        let tree = self.trees[rank].take().unwrap();
+        while let Some(None) = self.trees.last() {
+            self.trees.pop();
+        }
+        self.len -= 1;
+
+        let children = mem::take(&mut tree.borrow_mut().children);
+        for child in children {
+            child.borrow_mut().parent = Weak::new();
+            self.add_tree(child);
+        }
+
+        let node = Rc::try_unwrap(tree).ok().unwrap().into_inner();
+        Some(node.item)
+    }
+
+    fn peek(&self) -> Option<impl Deref<Target = T> + '_> {
+        let tree = self.trees[self.min_rank()?].as_ref().unwrap();
+        Some(Ref::map(tree.borrow(), |node| &node.item))
+    }
+
+    fn meld(&mut self, mut other: Self) {
+        for tree in mem::take(&mut other.trees).into_iter().flatten() {
+            self.add_tree(tree);
+        }
+        self.len += mem::take(&mut other.len);
+    }
+
+    fn decrease_key(&mut self, handle: &Handle<T>, item: T) -> bool {
+        let Some(locator) = handle.0.upgrade() else {
+            return false;
+        };
+        let mut node = locator.borrow().upgrade().unwrap();
+        if item > node.borrow().item {
+            return false;
+        }
+        node.borrow_mut().item = item;
+
+        // swap the item up, its locator goes along so handles stay valid
+        loop {
+            let parent = node.borrow().parent.upgrade();
+            let Some(parent) = parent else {
+                break;
+            };
+            if node.borrow().item >= parent.borrow().item {
+                break;
+            }
+            {
+                let mut n = node.borrow_mut();
+                let mut p = parent.borrow_mut();
+                mem::swap(&mut n.item, &mut p.item);
+                mem::swap(&mut n.locator, &mut p.locator);
+                *n.locator.borrow_mut() = Rc::downgrade(&node);
+                *p.locator.borrow_mut() = Rc::downgrade(&parent);
+            }
+            node = parent;
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+// the larger root becomes the child of rank k of the smaller one
+fn link<T: Ord>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let (parent, child) = if b.borrow().item < a.borrow().item {
+        (b, a)
+    } else {
+        (a, b)
+    };
+
+    child.borrow_mut().parent = Rc::downgrade(&parent);
+    parent.borrow_mut().children.push(child);
+    parent
+}
@@ -0,0 +1,253 @@
+use std::cell::{Ref, RefCell};
+use std::mem;
+use std::ops::Deref;
+use std::rc::{Rc, Weak};
+
+use super::MeldableHeap;
+
+type Link<T> = Rc<RefCell<Node<T>>>;
+
+struct Node<T> {
+    item: T,
+    parent: Weak<RefCell<Node<T>>>,
+    children: Vec<Link<T>>,
+    // position in `parent.children`
+    index: usize,
+    // next root in the root list, only set on roots
+    next_root: Option<Link<T>>,
+    // lost a child since it became a child itself
+    marked: bool,
+}
+
+/// Handle of an item in a `FibonacciHeap`.
+pub struct Handle<T>(Weak<RefCell<Node<T>>>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle(self.0.clone())
+    }
+}
+
+/// Fibonacci heap: a list of trees that is only tidied up by `pop`.
+///
+/// `push`, `meld` and `decrease_key` are O(1) amortized, `pop` is O(log n)
+/// amortized. The roots form a linked list with a tail pointer, so `meld`
+/// splices two lists together. `decrease_key` cuts the node off its parent,
+/// and cuts the parent too if it had already lost a child, which keeps the
+/// trees bushy.
+pub struct FibonacciHeap<T> {
+    head: Option<Link<T>>,
+    tail: Weak<RefCell<Node<T>>>,
+    // the smallest root
+    min: Option<Link<T>>,
+    len: usize,
+}
+
+impl<T: Ord> FibonacciHeap<T> {
+    pub fn new() -> Self {
+        Self {
+            head: None,
+            tail: Weak::new(),
+            min: None,
+            len: 0,
+        }
+    }
+
+    fn add_root(&mut self, node: Link<T>) {
+        {
+            let mut n = node.borrow_mut();
+            n.parent = Weak::new();
+            n.marked = false;
+        }
+        match self.tail.upgrade() {
+            Some(tail) => tail.borrow_mut().next_root = Some(node.clone()),
+            None => self.head = Some(node.clone()),
+        }
+        self.tail = Rc::downgrade(&node);
+        self.update_min(node);
+    }
+
+    fn update_min(&mut self, node: Link<T>) {
+        let smaller = match &self.min {
+            Some(min) => node.borrow().item < min.borrow().item,
+            None => true,
+        };
+        if smaller {
+            self.min = Some(node);
+        }
+    }
+
+    // links trees of equal degree until every degree is left once, the
+    // survivors become the new root list
+    fn consolidate(&mut self, trees: Vec<Link<T>>) {
+        let mut by_degree: Vec<Option<Link<T>>> = vec![];
+        for mut tree in trees {
+            loop {
+                let degree = tree.borrow().children.len();
+                if degree >= by_degree.len() {
+                    by_degree.resize_with(degree + 1, || None);
+                }
+                match by_degree[degree].take() {
+                    Some(other) => tree = link(tree, other),
+                    None => {
+                        by_degree[degree] = Some(tree);
+                        break;
+                    }
+                }
+            }
+        }
+
+        for tree in by_degree.into_iter().flatten() {
+            self.add_root(tree);
+        }
+    }
+
+    fn cut(&mut self, parent: &Link<T>, node: &Link<T>) {
+        let index = node.borrow().index;
+        {
+            let mut p = parent.borrow_mut();
+            p.children.swap_remove(index);
+            if let Some(moved) = p.children.get(index) {
+                moved.borrow_mut().index = index;
+            }
+        }
+        self.add_root(node.clone());
+    }
+}
+
+impl<T: Ord> Default for FibonacciHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> MeldableHeap<T> for FibonacciHeap<T> {
+    type Handle = Handle<T>;
+
+    fn push(&mut self, item: T) -> Handle<T> {
+        let node = Rc::new(RefCell::new(Node {
+            item,
+            parent: Weak::new(),
+            children: vec![],
+            index: 0,
+            next_root: None,
+            marked: false,
+        }));
+        let handle = Handle(Rc::downgrade(&node));
+        self.add_root(node);
+        self.len += 1;
+        handle
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let min = self.min.take()?;
+        self.len -= 1;
+
+        // every other root and the children of the minimum get consolidated
+        let mut trees = vec![];
+        let mut next = self.head.take();
+        self.tail = Weak::new();
+        while let Some(root) = next {
+            next = root.borrow_mut().next_root.take();
+            if !Rc::ptr_eq(&root, &min) {
+                trees.push(root);
+            }
+        }
+        trees.append(&mut min.borrow_mut().children);
+        self.consolidate(trees);
+
+        let node = Rc::try_unwrap(min).ok().unwrap().into_inner();
+        Some(node.item)
+    }
+
+    fn peek(&self) -> Option<impl Deref<Target = T> + '_> {
+        let min = self.min.as_ref()?;
+        Some(Ref::map(min.borrow(), |node| &node.item))
+    }
+
+    fn meld(&mut self, mut other: Self) {
+        let Some(other_head) = other.head.take() else {
+            return;
+        };
+        match self.tail.upgrade() {
+            Some(tail) => tail.borrow_mut().next_root = Some(other_head),
+            None => self.head = Some(other_head),
+        }
+        self.tail = mem::take(&mut other.tail);
+        if let Some(other_min) = other.min.take() {
+            self.update_min(other_min);
+        }
+        self.len += mem::take(&mut other.len);
+    }
+
+    fn decrease_key(&mut self, handle: &Handle<T>, item: T) -> bool {
+        let Some(node) = handle.0.upgrade() else {
+            return false;
+        };
+        if item > node.borrow().item {
+            return false;
+        }
+        node.borrow_mut().item = item;
+
+        let parent = node.borrow().parent.upgrade();
+        match parent {
+            Some(parent) if node.borrow().item < parent.borrow().item => {
+                self.cut(&parent, &node);
+
+                // cascading cut: a marked parent loses its second child and
+                // moves to the roots as well
+                let mut node = parent;
+                loop {
+                    let parent = node.borrow().parent.upgrade();
+                    let Some(parent) = parent else {
+                        break;
+                    };
+                    if !node.borrow().marked {
+                        node.borrow_mut().marked = true;
+                        break;
+                    }
+                    self.cut(&parent, &node);
+                    node = parent;
+                }
+            }
+            Some(_) => {}
+            None => self.update_min(node),
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T> Drop for FibonacciHeap<T> {
+    fn drop(&mut self) {
+        // the root list and the trees can both be long, don't recurse
+        self.min = None;
+        let mut stack: Vec<Link<T>> = self.head.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let mut n = node.borrow_mut();
+            stack.extend(n.next_root.take());
+            stack.append(&mut n.children);
+        }
+    }
+}
+
+// the larger root becomes the last child of the smaller one
+fn link<T: Ord>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let (parent, child) = if b.borrow().item < a.borrow().item {
+        (b, a)
+    } else {
+        (a, b)
+    };
+
+    {
+        let mut c = child.borrow_mut();
+        c.parent = Rc::downgrade(&parent);
+        c.index = parent.borrow().children.len();
+        c.marked = false;
+    }
+    parent.borrow_mut().children.push(child);
+    parent
+}
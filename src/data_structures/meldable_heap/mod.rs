@@ -0,0 +1,219 @@
+use std::ops::Deref;
+
+pub mod binomial;
+pub mod fibonacci;
+pub mod pairing;
+
+pub use binomial::BinomialHeap;
+pub use fibonacci::FibonacciHeap;
+pub use pairing::PairingHeap;
+
+/// Min heap that can be melded with another heap of the same kind without
+/// copying its items. For a max heap use `std::cmp::Reverse` items.
+///
+/// `push` returns a handle for `decrease_key`. A handle is weak: once its
+/// item has been popped, `decrease_key` on it returns false. After `meld` the
+/// handles of `other` belong to the melded heap. Using a handle with a heap
+/// it doesn't belong to is a logic error; the heaps may then misbehave, but
+/// not in an unsafe way.
+pub trait MeldableHeap<T: Ord>: Default {
+    type Handle: Clone;
+
+    fn push(&mut self, item: T) -> Self::Handle;
+
+    fn pop(&mut self) -> Option<T>;
+
+    fn peek(&self) -> Option<impl Deref<Target = T> + '_>;
+
+    /// Moves all items of `other` into `self`.
+    fn meld(&mut self, other: Self);
+
+    /// Replaces the item of `handle` with a smaller or equal one. Returns
+    /// false, without changing anything, if the item is gone or `item` is
+    /// larger.
+    fn decrease_key(&mut self, handle: &Self::Handle, item: T) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// shared by every implementation, `Heap::new_min` is the reference
+#[cfg(test)]
+macro_rules! meldable_heap_tests {
+    ($($name:ident: $heap:ty,)*) => {$(
+        mod $name {
+            use super::super::heap::Heap;
+            use super::MeldableHeap;
+            use crate::sorting::Rng;
+
+            type H = $heap;
+
+            // small values, so there are plenty of ties
+            fn random(len: usize, seed: u64) -> Vec<u64> {
+                let mut rng = Rng::new(seed);
+                (0..len).map(|_| rng.below(1000)).collect()
+            }
+
+            fn drain(heap: &mut H) -> Vec<u64> {
+                std::iter::from_fn(|| heap.pop()).collect()
+            }
+
+            fn drain_reference(heap: &mut Heap<u64>) -> Vec<u64> {
+                std::iter::from_fn(|| heap.pop()).collect()
+            }
+
+            #[test]
+            fn empty() {
+                let mut heap = H::default();
+                assert!(heap.is_empty());
+                assert!(heap.peek().is_none());
+                assert_eq!(None, heap.pop());
+            }
+
+            #[test]
+            fn push_pop() {
+                for (len, seed) in [(1, 1), (2, 2), (10, 3), (1000, 4)] {
+                    let mut heap = H::default();
+                    let mut reference = Heap::new_min();
+                    for x in random(len, seed) {
+                        heap.push(x);
+                        reference.add(x);
+                        assert_eq!(reference.peek(), heap.peek().as_deref());
+                    }
+                    assert_eq!(len, heap.len());
+                    assert_eq!(drain_reference(&mut reference), drain(&mut heap));
+                }
+            }
+
+            #[test]
+            fn interleaved() {
+                let mut heap = H::default();
+                let mut reference = Heap::new_min();
+                for (i, x) in random(5000, 5).into_iter().enumerate() {
+                    if x % 3 == 0 {
+                        assert_eq!(reference.pop(), heap.pop());
+                    } else {
+                        heap.push(x);
+                        reference.add(x);
+                    }
+                    if i % 100 == 0 {
+                        assert_eq!(reference.len(), heap.len());
+                        assert_eq!(reference.peek(), heap.peek().as_deref());
+                    }
+                }
+                assert_eq!(drain_reference(&mut reference), drain(&mut heap));
+            }
+
+            #[test]
+            fn meld() {
+                let mut a = H::default();
+                let mut b = H::default();
+                let mut reference = Heap::new_min();
+                for x in random(300, 6) {
+                    a.push(x);
+                    reference.add(x);
+                }
+                // pop a few so the trees aren't fresh
+                for _ in 0..50 {
+                    assert_eq!(reference.pop(), a.pop());
+                }
+                for x in random(200, 7) {
+                    b.push(x);
+                    reference.add(x);
+                }
+
+                a.meld(b);
+                a.meld(H::default());
+                assert_eq!(450, a.len());
+                assert_eq!(drain_reference(&mut reference), drain(&mut a));
+
+                let mut empty = H::default();
+                let mut c = H::default();
+                c.push(3);
+                empty.meld(c);
+                assert_eq!(Some(3), empty.pop());
+            }
+
+            #[test]
+            fn meld_keeps_handles() {
+                let mut a = H::default();
+                let mut b = H::default();
+                for x in 10..20 {
+                    a.push(x);
+                }
+                let handle = b.push(100);
+                b.push(50);
+
+                a.meld(b);
+                assert!(a.decrease_key(&handle, 1));
+                assert_eq!(Some(1), a.pop());
+            }
+
+            #[test]
+            fn decrease_key() {
+                let mut heap = H::default();
+                let mut items = random(2000, 8);
+                let handles: Vec<_> = items.iter().map(|&x| heap.push(x + 1000)).collect();
+
+                // pop some, then decrease the rest in random order
+                for _ in 0..100 {
+                    heap.pop();
+                }
+                let mut reference = Heap::new_min();
+                let mut alive = 0;
+                for (i, x) in random(2000, 9).into_iter().enumerate() {
+                    let j = (x as usize * 7 + i) % items.len();
+                    if heap.decrease_key(&handles[j], items[j]) {
+                        alive += 1;
+                    }
+                    items[j] = items[j].saturating_sub(1);
+                }
+                assert!(alive > 0);
+
+                // a larger item is refused
+                let big = handles.iter().find(|h| heap.decrease_key(h, 0)).unwrap();
+                assert!(!heap.decrease_key(big, 5000));
+                assert_eq!(Some(0), heap.pop());
+
+                let rest = drain(&mut heap);
+                for &x in &rest {
+                    reference.add(x);
+                }
+                assert_eq!(1899, rest.len());
+                assert_eq!(drain_reference(&mut reference), rest);
+            }
+
+            #[test]
+            fn stale_handle() {
+                let mut heap = H::default();
+                let handle = heap.push(1);
+                heap.push(2);
+                assert_eq!(Some(1), heap.pop());
+                assert!(!heap.decrease_key(&handle, 0));
+                assert_eq!(Some(2), heap.pop());
+            }
+
+            #[test]
+            fn large() {
+                let mut heap = H::default();
+                let n = 100_000;
+                for x in (0..n).rev() {
+                    heap.push(x);
+                }
+                assert_eq!(Some(0), heap.pop());
+                assert_eq!(n as usize - 1, heap.len());
+                // dropping a deep or wide heap must not overflow the stack
+            }
+        }
+    )*};
+}
+
+#[cfg(test)]
+meldable_heap_tests! {
+    pairing_heap: super::PairingHeap<u64>,
+    binomial_heap: super::BinomialHeap<u64>,
+    fibonacci_heap: super::FibonacciHeap<u64>,
+}
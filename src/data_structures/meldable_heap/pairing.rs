@@ -0,0 +1,169 @@
+use std::cell::{Ref, RefCell};
+use std::mem;
+use std::ops::Deref;
+use std::rc::{Rc, Weak};
+
+use super::MeldableHeap;
+
+type Link<T> = Rc<RefCell<Node<T>>>;
+
+struct Node<T> {
+    item: T,
+    parent: Weak<RefCell<Node<T>>>,
+    children: Vec<Link<T>>,
+    // position in `parent.children`
+    index: usize,
+}
+
+/// Handle of an item in a `PairingHeap`.
+pub struct Handle<T>(Weak<RefCell<Node<T>>>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle(self.0.clone())
+    }
+}
+
+/// Pairing heap: a single tree where every node is no larger than its
+/// children.
+///
+/// `push`, `meld` and `decrease_key` link two trees in O(1), `pop` pairs up
+/// the children of the root left to right and melds the pairs right to
+/// left, O(log n) amortized.
+pub struct PairingHeap<T> {
+    root: Option<Link<T>>,
+    len: usize,
+}
+
+impl<T: Ord> PairingHeap<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    fn add_tree(&mut self, tree: Link<T>) {
+        self.root = Some(match self.root.take() {
+            Some(root) => link(root, tree),
+            None => tree,
+        });
+    }
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> MeldableHeap<T> for PairingHeap<T> {
+    type Handle = Handle<T>;
+
+    fn push(&mut self, item: T) -> Handle<T> {
+        let node = Rc::new(RefCell::new(Node {
+            item,
+            parent: Weak::new(),
+            children: vec![],
+            index: 0,
+        }));
+        let handle = Handle(Rc::downgrade(&node));
+        self.add_tree(node);
+        self.len += 1;
+        handle
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        self.len -= 1;
+
+        let children = mem::take(&mut root.borrow_mut().children);
+        for child in &children {
+            child.borrow_mut().parent = Weak::new();
+        }
+
+        // link pairs left to right, then meld the pairs right to left
+        let mut pairs = Vec::with_capacity(children.len() / 2 + 1);
+        let mut children = children.into_iter();
+        while let Some(a) = children.next() {
+            pairs.push(match children.next() {
+                Some(b) => link(a, b),
+                None => a,
+            });
+        }
+        self.root = pairs.into_iter().rev().reduce(link);
+
+        let node = Rc::try_unwrap(root).ok().unwrap().into_inner();
+        Some(node.item)
+    }
+
+    fn peek(&self) -> Option<impl Deref<Target = T> + '_> {
+        let root = self.root.as_ref()?;
+        Some(Ref::map(root.borrow(), |node| &node.item))
+    }
+
+    fn meld(&mut self, mut other: Self) {
+        if let Some(tree) = other.root.take() {
+            self.add_tree(tree);
+        }
+        self.len += mem::take(&mut other.len);
+    }
+
+    fn decrease_key(&mut self, handle: &Handle<T>, item: T) -> bool {
+        let Some(node) = handle.0.upgrade() else {
+            return false;
+        };
+        if item > node.borrow().item {
+            return false;
+        }
+        node.borrow_mut().item = item;
+
+        // cut the subtree off its parent and link it with the root
+        let parent = node.borrow().parent.upgrade();
+        if let Some(parent) = parent {
+            if node.borrow().item < parent.borrow().item {
+                cut(&parent, &node);
+                self.add_tree(node);
+            }
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T> Drop for PairingHeap<T> {
+    fn drop(&mut self) {
+        // the tree can be a long chain, don't recurse
+        let mut stack: Vec<Link<T>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            stack.append(&mut node.borrow_mut().children);
+        }
+    }
+}
+
+// the larger root becomes the last child of the smaller one
+fn link<T: Ord>(a: Link<T>, b: Link<T>) -> Link<T> {
+    let (parent, child) = if b.borrow().item < a.borrow().item {
+        (b, a)
+    } else {
+        (a, b)
+    };
+
+    {
+        let mut c = child.borrow_mut();
+        c.parent = Rc::downgrade(&parent);
+        c.index = parent.borrow().children.len();
+    }
+    parent.borrow_mut().children.push(child);
+    parent
+}
+
+fn cut<T>(parent: &Link<T>, node: &Link<T>) {
+    let index = node.borrow().index;
+    let mut p = parent.borrow_mut();
+    p.children.swap_remove(index);
+    if let Some(moved) = p.children.get(index) {
+        moved.borrow_mut().index = index;
+    }
+    node.borrow_mut().parent = Weak::new();
+}
@@ -1,4 +1,6 @@
 mod b_tree;
 mod binary_search_tree;
-mod heap;
+pub mod heap;
+pub mod indexed_heap;
 mod linked_list;
+pub mod meldable_heap;
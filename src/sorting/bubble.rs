@@ -1,19 +1,66 @@
-pub fn bubble_sort(arr: &mut Vec<i32>) {
-    let mut max = arr.len();
-    let mut sorted = false;
-
-    while !sorted {
-        sorted = true;
-        for i in 1..max {
-            if arr[i - 1] > arr[i] {
-                arr.swap(i - 1, i);
-                sorted = false;
+use std::cmp::Ordering;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::Sorter;
+
+pub struct BubbleSort;
+
+impl Sorter for BubbleSort {
+    const STABLE: bool = true;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_observed(arr, compare, &mut ());
+    }
+}
+
+impl ObservedSorter for BubbleSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        let mut trace = Trace::new(observer);
+        let mut max = arr.len();
+        let mut sorted = false;
+
+        while !sorted && max > 1 {
+            sorted = true;
+            for i in 1..max {
+                trace.compare(i - 1, i);
+                if compare(&arr[i - 1], &arr[i]) == Ordering::Greater {
+                    arr.swap(i - 1, i);
+                    trace.swap(i - 1, i);
+                    sorted = false;
+                }
             }
-        }
 
-        max -= 1;
+            max -= 1;
+        }
     }
 }
+
+pub fn bubble_sort<T: Ord>(arr: &mut [T]) {
+    BubbleSort.sort(arr);
+}
+
+pub fn bubble_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    BubbleSort.sort_by(arr, compare);
+}
+
+pub fn bubble_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    BubbleSort.sort_by_key(arr, f);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -24,4 +71,39 @@ mod tests {
         bubble_sort(&mut arr);
         assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
     }
+
+    #[test]
+    fn empty() {
+        let mut arr: Vec<i32> = vec![];
+        bubble_sort(&mut arr);
+        assert!(arr.is_empty());
+    }
+
+    #[test]
+    fn sub_slice() {
+        let mut arr = vec![9, 6, 5, 3, 1, 0];
+        bubble_sort(&mut arr[1..5]);
+        assert_eq!(arr, vec![9, 1, 3, 5, 6, 0]);
+    }
+
+    #[test]
+    fn descending() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        bubble_sort_by(&mut arr, |a, b| b.cmp(a));
+        assert_eq!(arr, vec![8, 7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn by_key() {
+        let mut arr = vec![("c", 3), ("a", 1), ("b", 2), ("a", 0)];
+        bubble_sort_by_key(&mut arr, |&(_, n)| n);
+        assert_eq!(arr, vec![("a", 0), ("a", 1), ("b", 2), ("c", 3)]);
+    }
+
+    #[test]
+    fn stable() {
+        let mut arr = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        bubble_sort_by_key(&mut arr, |&(k, _)| k);
+        assert_eq!(arr, vec![(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+    }
 }
@@ -0,0 +1,175 @@
+use std::ops::RangeInclusive;
+
+use super::radix::apply_permutation;
+use super::{insertion, SortError};
+
+/// Bucket sort for `f64` values spread evenly over `range`. Stable.
+///
+/// The range is split into `n` equal buckets, each bucket is insertion
+/// sorted. O(n) on average for uniform input, O(n^2) if everything lands in
+/// one bucket.
+///
+/// Returns an error, without touching `arr`, for a NaN or a value outside
+/// `range`.
+pub fn bucket_sort(arr: &mut [f64], range: RangeInclusive<f64>) -> Result<(), SortError> {
+    bucket_sort_by_key(arr, range, |&x| x)
+}
+
+/// Bucket sort of records by an `f64` key spread evenly over `range`. Stable.
+pub fn bucket_sort_by_key<T, F>(
+    arr: &mut [T],
+    range: RangeInclusive<f64>,
+    mut f: F,
+) -> Result<(), SortError>
+where
+    F: FnMut(&T) -> f64,
+{
+    let (min, max) = range.into_inner();
+    if !(min.is_finite() && max.is_finite() && min <= max) {
+        return Err(SortError::InvalidRange);
+    }
+
+    let mut keys = Vec::with_capacity(arr.len());
+    for (index, x) in arr.iter().enumerate() {
+        let key = f(x);
+        if key.is_nan() {
+            return Err(SortError::NaN { index });
+        }
+        if key < min || key > max {
+            return Err(SortError::KeyOutOfRange { index });
+        }
+        keys.push(key);
+    }
+
+    let n = arr.len();
+    if n < 2 {
+        return Ok(());
+    }
+
+    let width = max - min;
+    let bucket = |key: f64| {
+        if width == 0.0 {
+            return 0;
+        }
+        // `max` itself would be bucket n
+        (((key - min) / width * n as f64) as usize).min(n - 1)
+    };
+
+    let mut starts = vec![0; n + 1];
+    for &key in &keys {
+        starts[bucket(key) + 1] += 1;
+    }
+    for b in 1..=n {
+        starts[b] += starts[b - 1];
+    }
+
+    // (key, old position) grouped by bucket, in input order within a bucket
+    let mut next = starts.clone();
+    let mut entries = vec![(0.0, 0); n];
+    for (i, &key) in keys.iter().enumerate() {
+        let b = bucket(key);
+        entries[next[b]] = (key, i);
+        next[b] += 1;
+    }
+
+    for b in 0..n {
+        insertion::sort(&mut entries[starts[b]..starts[b + 1]], &mut |a, b| {
+            a.0 < b.0
+        });
+    }
+
+    let mut perm: Vec<usize> = entries.into_iter().map(|(_, i)| i).collect();
+    apply_permutation(arr, &mut perm);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Rng;
+
+    fn uniform(len: usize, seed: u64) -> Vec<f64> {
+        let mut rng = Rng::new(seed);
+        (0..len)
+            .map(|_| (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64)
+            .collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![0.42, 0.32, 0.23, 0.52, 0.25, 0.47, 0.51, 1.0, 0.0];
+        bucket_sort(&mut arr, 0.0..=1.0).unwrap();
+        assert_eq!(
+            arr,
+            vec![0.0, 0.23, 0.25, 0.32, 0.42, 0.47, 0.51, 0.52, 1.0]
+        );
+    }
+
+    #[test]
+    fn uniform_input() {
+        let mut arr: Vec<f64> = uniform(10_000, 1)
+            .iter()
+            .map(|x| x * 200.0 - 100.0)
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort_by(f64::total_cmp);
+
+        bucket_sort(&mut arr, -100.0..=100.0).unwrap();
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn skewed_input() {
+        let mut arr: Vec<f64> = uniform(1000, 2).iter().map(|x| x.powi(8)).collect();
+        let mut expected = arr.clone();
+        expected.sort_by(f64::total_cmp);
+
+        bucket_sort(&mut arr, 0.0..=1.0).unwrap();
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn stable_records() {
+        let mut arr = vec![("a", 0.5), ("b", 0.1), ("c", 0.5), ("d", 0.1), ("e", 0.9)];
+        bucket_sort_by_key(&mut arr, 0.0..=1.0, |&(_, x)| x).unwrap();
+        assert_eq!(
+            arr,
+            vec![("b", 0.1), ("d", 0.1), ("a", 0.5), ("c", 0.5), ("e", 0.9)]
+        );
+    }
+
+    #[test]
+    fn degenerate_range() {
+        let mut arr = vec![2.0, 2.0, 2.0];
+        bucket_sort(&mut arr, 2.0..=2.0).unwrap();
+        assert_eq!(arr, vec![2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let mut arr = vec![0.5, 1.5, 0.2];
+        assert_eq!(
+            bucket_sort(&mut arr, 0.0..=1.0),
+            Err(SortError::KeyOutOfRange { index: 1 })
+        );
+        assert_eq!(arr, vec![0.5, 1.5, 0.2]);
+
+        assert_eq!(
+            bucket_sort(&mut [0.5, f64::NAN], 0.0..=1.0),
+            Err(SortError::NaN { index: 1 })
+        );
+        assert_eq!(
+            bucket_sort(&mut [f64::NEG_INFINITY], 0.0..=1.0),
+            Err(SortError::KeyOutOfRange { index: 0 })
+        );
+        assert_eq!(
+            bucket_sort(&mut [0.5], 1.0..=0.0),
+            Err(SortError::InvalidRange)
+        );
+        assert_eq!(
+            bucket_sort(&mut [0.5], 0.0..=f64::INFINITY),
+            Err(SortError::InvalidRange)
+        );
+    }
+}
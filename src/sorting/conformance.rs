@@ -0,0 +1,224 @@
+//! Conformance suite every sorter in this module has to pass.
+//!
+//! Each sorter is run on all `Distribution`s for a range of lengths and the
+//! result is compared with `slice::sort_by_key`. Elements are `(key, index)`
+//! records sorted by key only, so the suite can also tell whether the sort
+//! kept equal keys in order and whether it lost or duplicated anything.
+
+use super::inputs::Distribution;
+use super::Sorter;
+
+pub(crate) type Record = (u64, usize);
+
+const LENGTHS: [usize; 19] = [
+    0, 1, 2, 3, 4, 5, 7, 8, 15, 16, 17, 31, 32, 33, 64, 65, 100, 255, 1000,
+];
+const SEEDS: [u64; 2] = [1, 2];
+
+/// Runs `sort` on every input. `sort` has to order the records by `.0`.
+pub(crate) fn check<F>(name: &str, stable: bool, mut sort: F)
+where
+    F: FnMut(&mut [Record]),
+{
+    for distribution in Distribution::ALL {
+        for len in LENGTHS {
+            for seed in SEEDS {
+                let keys = distribution.generate(len, seed);
+                // narrow keys as well, so every shape has plenty of duplicates
+                for modulo in [u64::MAX, 10] {
+                    let input: Vec<Record> = keys.iter().map(|k| k % modulo).zip(0..).collect();
+                    let context = format!(
+                        "{} on {} input, len {}, seed {}, keys mod {}",
+                        name,
+                        distribution.name(),
+                        len,
+                        seed,
+                        modulo
+                    );
+
+                    let mut actual = input.clone();
+                    sort(&mut actual);
+                    check_result(&input, &actual, stable, &context);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn check_sorter<S: Sorter>(name: &str, sorter: &S) {
+    check(name, S::STABLE, |v| sorter.sort_by_key(v, |r| r.0));
+}
+
+fn check_result(input: &[Record], actual: &[Record], stable: bool, context: &str) {
+    let mut expected = input.to_vec();
+    expected.sort_by_key(|r| r.0);
+
+    assert_eq!(actual.len(), input.len(), "{}: length changed", context);
+    assert!(
+        actual.iter().map(|r| r.0).eq(expected.iter().map(|r| r.0)),
+        "{}: keys are not sorted",
+        context
+    );
+
+    // same elements, each exactly once
+    let mut seen = vec![false; input.len()];
+    for &(key, index) in actual {
+        assert!(
+            index < input.len() && !seen[index] && input[index].0 == key,
+            "{}: not a permutation of the input",
+            context
+        );
+        seen[index] = true;
+    }
+
+    if stable {
+        assert_eq!(actual, &expected[..], "{}: not stable", context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{
+        bucket_sort_by_key, counting_sort_by_key, float_sort_by_key, msd_radix_sort_by_key,
+        radix_sort_by_key, BottomUpMergeSort, BubbleSort, GapSequence, HeapSort, InsertionSort,
+        MergeSort, NanPolicy, ParallelMergeSort, QuickSort, ShellSort, TimSort,
+    };
+
+    // every key below 2^53 is exactly an f64
+    fn exact_in_f64(v: &[Record]) -> bool {
+        v.iter().all(|r| r.0 < 1 << f64::MANTISSA_DIGITS)
+    }
+
+    #[test]
+    fn bubble() {
+        check_sorter("bubble", &BubbleSort);
+    }
+
+    #[test]
+    fn insertion() {
+        check_sorter("insertion", &InsertionSort);
+    }
+
+    #[test]
+    fn heap() {
+        check_sorter("heap", &HeapSort);
+    }
+
+    #[test]
+    fn merge() {
+        check_sorter("merge", &MergeSort);
+        check_sorter("bottom-up merge", &BottomUpMergeSort);
+    }
+
+    #[test]
+    fn quick() {
+        check_sorter("quick", &QuickSort);
+    }
+
+    #[test]
+    fn tim() {
+        check_sorter("tim", &TimSort);
+    }
+
+    #[test]
+    fn shell() {
+        for gaps in [
+            GapSequence::Shell,
+            GapSequence::Knuth,
+            GapSequence::Sedgewick,
+            GapSequence::Ciura,
+            GapSequence::Custom(&[4, 9]),
+        ] {
+            check_sorter(&format!("shell {:?}", gaps), &ShellSort::new(gaps));
+        }
+    }
+
+    #[test]
+    fn parallel_merge() {
+        let sorter = ParallelMergeSort::new().threads(4).sequential_cutoff(8);
+        check("parallel merge", true, |v| sorter.sort_by_key(v, |r| r.0));
+    }
+
+    #[test]
+    fn radix() {
+        check("radix", true, |v| radix_sort_by_key(v, |r| r.0));
+    }
+
+    #[test]
+    fn counting() {
+        // most generated keys are far too spread out for a counting sort, those
+        // go through the radix sort, the keys narrowed to 0..10 don't
+        check("counting", true, |v| {
+            let max = v.iter().map(|r| r.0).max().unwrap_or(0);
+            if max < 1 << 16 {
+                counting_sort_by_key(v, 0..=max, |r| r.0).unwrap();
+            } else {
+                radix_sort_by_key(v, |r| r.0);
+            }
+        });
+    }
+
+    #[test]
+    fn msd_radix() {
+        check("msd radix", false, |v| {
+            // big endian bytes order the same way as the keys
+            let mut bytes: Vec<([u8; 8], usize)> =
+                v.iter().map(|r| (r.0.to_be_bytes(), r.1)).collect();
+            msd_radix_sort_by_key(&mut bytes, |r| &r.0);
+            for (r, (key, index)) in v.iter_mut().zip(bytes) {
+                *r = (u64::from_be_bytes(key), index);
+            }
+        });
+    }
+
+    #[test]
+    fn float() {
+        // keys too large for an f64 would collapse into ties, those few inputs
+        // go through the radix sort
+        check("float", true, |v| {
+            if exact_in_f64(v) {
+                float_sort_by_key(v, NanPolicy::Error, |r| r.0 as f64).unwrap();
+            } else {
+                radix_sort_by_key(v, |r| r.0);
+            }
+        });
+    }
+
+    #[test]
+    fn bucket() {
+        // same as for the float sort
+        check("bucket", true, |v| {
+            if exact_in_f64(v) {
+                let max = v.iter().map(|r| r.0).max().unwrap_or(0) as f64;
+                bucket_sort_by_key(v, 0.0..=max, |r| r.0 as f64).unwrap();
+            } else {
+                radix_sort_by_key(v, |r| r.0);
+            }
+        });
+    }
+
+    // the suite has to catch broken sorters, not just pass good ones
+    #[test]
+    #[should_panic(expected = "not stable")]
+    fn catches_unstable() {
+        check("unstable", true, |v| HeapSort.sort_by_key(v, |r| r.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "keys are not sorted")]
+    fn catches_unsorted() {
+        check("reverse", false, |v| v.reverse());
+    }
+
+    #[test]
+    #[should_panic(expected = "not a permutation")]
+    fn catches_lost_elements() {
+        check("dup", false, |v| {
+            v.sort_by_key(|r| r.0);
+            if v.len() > 1 && v[0].0 == v[1].0 {
+                v[1] = v[0];
+            }
+        });
+    }
+}
@@ -0,0 +1,190 @@
+use std::ops::RangeInclusive;
+
+use super::radix::apply_permutation;
+use super::SortError;
+
+// most counters a sort allocates, 128 MiB on 64-bit targets
+const MAX_RANGE: i128 = 1 << 24;
+
+/// Counting sort for integer keys in `range`. Stable, O(n + k) where `k` is
+/// the size of the range.
+///
+/// Returns an error, without touching `arr`, if a key is outside `range`, and
+/// `SortError::RangeTooLarge` if `range` holds more than 2^24 keys or its
+/// counters can't be allocated (a radix sort is the better choice then).
+pub fn counting_sort<K>(arr: &mut [K], range: RangeInclusive<K>) -> Result<(), SortError>
+where
+    K: Copy + TryInto<i128>,
+{
+    counting_sort_by_key(arr, range, |&k| k)
+}
+
+/// Counting sort of records by an integer key in `range`. Stable.
+pub fn counting_sort_by_key<T, K, F>(
+    arr: &mut [T],
+    range: RangeInclusive<K>,
+    mut f: F,
+) -> Result<(), SortError>
+where
+    K: Copy + TryInto<i128>,
+    F: FnMut(&T) -> K,
+{
+    let widen = |k: K| k.try_into().map_err(|_| SortError::InvalidRange);
+    let min = widen(*range.start())?;
+    let max = widen(*range.end())?;
+    if min > max {
+        return Err(SortError::InvalidRange);
+    }
+    if max - min >= MAX_RANGE {
+        return Err(SortError::RangeTooLarge);
+    }
+
+    // validate everything before anything is moved
+    let mut offsets = Vec::with_capacity(arr.len());
+    for (index, x) in arr.iter().enumerate() {
+        match widen(f(x)) {
+            Ok(k) if min <= k && k <= max => offsets.push((k - min) as usize),
+            _ => return Err(SortError::KeyOutOfRange { index }),
+        }
+    }
+
+    let len = (max - min) as usize + 1;
+    let mut counts = Vec::new();
+    counts
+        .try_reserve_exact(len)
+        .map_err(|_| SortError::RangeTooLarge)?;
+    counts.resize(len, 0);
+    for &offset in &offsets {
+        counts[offset] += 1;
+    }
+
+    let mut next = 0;
+    for count in counts.iter_mut() {
+        let c = *count;
+        *count = next;
+        next += c;
+    }
+
+    // perm[i] is the old position of the element that ends up at i
+    let mut perm = vec![0; arr.len()];
+    for (i, &offset) in offsets.iter().enumerate() {
+        perm[counts[offset]] = i;
+        counts[offset] += 1;
+    }
+    apply_permutation(arr, &mut perm);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        counting_sort(&mut arr, 0..=10).unwrap();
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn negative_and_duplicate_keys() {
+        let mut arr: Vec<i8> = vec![3, -2, 0, -128, 3, 127, -2];
+        counting_sort(&mut arr, i8::MIN..=i8::MAX).unwrap();
+        assert_eq!(arr, vec![-128, -2, -2, 0, 3, 3, 127]);
+
+        let mut arr: Vec<usize> = vec![12, 10, 11, 10];
+        counting_sort(&mut arr, 10..=12).unwrap();
+        assert_eq!(arr, vec![10, 10, 11, 12]);
+    }
+
+    #[test]
+    fn stable_records() {
+        let mut people = vec![
+            ("ann", 31),
+            ("bob", 25),
+            ("cid", 31),
+            ("dan", 25),
+            ("eve", 40),
+        ];
+        counting_sort_by_key(&mut people, 0..=150, |&(_, age)| age).unwrap();
+        assert_eq!(
+            people,
+            vec![
+                ("bob", 25),
+                ("dan", 25),
+                ("ann", 31),
+                ("cid", 31),
+                ("eve", 40)
+            ]
+        );
+    }
+
+    #[test]
+    fn key_out_of_range() {
+        let mut arr = vec![1, 5, 2, 11, 3];
+        assert_eq!(
+            counting_sort(&mut arr, 0..=10),
+            Err(SortError::KeyOutOfRange { index: 3 })
+        );
+        // left unchanged
+        assert_eq!(arr, vec![1, 5, 2, 11, 3]);
+
+        let mut arr = vec![-1];
+        assert_eq!(
+            counting_sort(&mut arr, 0..=10),
+            Err(SortError::KeyOutOfRange { index: 0 })
+        );
+    }
+
+    #[test]
+    fn invalid_range() {
+        let mut arr = vec![1, 2];
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 10..=0;
+        assert_eq!(counting_sort(&mut arr, empty), Err(SortError::InvalidRange));
+        assert_eq!(
+            counting_sort(&mut [1u128], 0..=u128::MAX),
+            Err(SortError::InvalidRange)
+        );
+    }
+
+    #[test]
+    fn range_too_large() {
+        assert_eq!(
+            counting_sort(&mut [1u64], 0..=u64::MAX),
+            Err(SortError::RangeTooLarge)
+        );
+        assert_eq!(
+            counting_sort(&mut [7u32, 1], 0..=u32::MAX),
+            Err(SortError::RangeTooLarge)
+        );
+        assert_eq!(
+            counting_sort(&mut [1i64, 0], -(1 << 40)..=(1 << 40)),
+            Err(SortError::RangeTooLarge)
+        );
+        assert_eq!(
+            counting_sort(&mut [0u32], 0..=1 << 24),
+            Err(SortError::RangeTooLarge)
+        );
+    }
+
+    #[test]
+    fn range_independent_of_input() {
+        // a few keys spread over a wide range
+        let mut arr: Vec<u32> = (0..100).rev().map(|x| x * 1000).collect();
+        counting_sort(&mut arr, 0..=100_000).unwrap();
+        assert_eq!(arr, (0..100).map(|x| x * 1000).collect::<Vec<_>>());
+
+        let mut arr = vec![(1 << 24) - 1, 0, 1];
+        counting_sort(&mut arr, 0..=(1 << 24) - 1).unwrap();
+        assert_eq!(arr, vec![0, 1, (1 << 24) - 1]);
+    }
+
+    #[test]
+    fn empty() {
+        let mut arr: Vec<u8> = vec![];
+        counting_sort(&mut arr, 0..=255).unwrap();
+        assert!(arr.is_empty());
+    }
+}
@@ -0,0 +1,31 @@
+use std::error::Error;
+use std::fmt;
+
+/// Why a sort refused its input. The slice is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortError {
+    /// The declared key range is empty or can't be used.
+    InvalidRange,
+    /// The declared key range needs more counters than the sort will
+    /// allocate, see `counting_sort`.
+    RangeTooLarge,
+    /// The key of the element at `index` is outside the declared range.
+    KeyOutOfRange { index: usize },
+    /// The key of the element at `index` is NaN.
+    NaN { index: usize },
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortError::InvalidRange => write!(f, "invalid key range"),
+            SortError::RangeTooLarge => write!(f, "key range too large"),
+            SortError::KeyOutOfRange { index } => {
+                write!(f, "key of element {} is outside the declared range", index)
+            }
+            SortError::NaN { index } => write!(f, "key of element {} is NaN", index),
+        }
+    }
+}
+
+impl Error for SortError {}
@@ -0,0 +1,470 @@
+use std::cmp::Ordering;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use super::{heap, MergeBuffer, MergeSort};
+
+// used to give every run file of this process a unique name
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+// runs merged at once by default, each one holds an open file and a buffer
+const DEFAULT_FAN_IN: usize = 64;
+
+/// How records are read from and written to a byte stream.
+pub trait RecordCodec {
+    type Record;
+
+    /// Reads the next record, `Ok(None)` on a clean end of input.
+    fn read<R: BufRead>(&self, reader: &mut R) -> io::Result<Option<Self::Record>>;
+
+    fn write<W: Write>(&self, writer: &mut W, record: &Self::Record) -> io::Result<()>;
+
+    /// Approximate memory used by a record, counted against the budget.
+    fn size(&self, record: &Self::Record) -> usize;
+}
+
+/// Newline delimited UTF-8 records, the `\n` is not part of the record.
+pub struct LineCodec;
+
+impl RecordCodec for LineCodec {
+    type Record = String;
+
+    fn read<R: BufRead>(&self, reader: &mut R) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, record: &String) -> io::Result<()> {
+        writer.write_all(record.as_bytes())?;
+        writer.write_all(b"\n")
+    }
+
+    fn size(&self, record: &String) -> usize {
+        mem::size_of::<String>() + record.len()
+    }
+}
+
+/// Binary records of exactly `width` bytes each.
+pub struct FixedWidthCodec {
+    width: usize,
+}
+
+impl FixedWidthCodec {
+    pub fn new(width: usize) -> Self {
+        assert!(width > 0, "record width must not be zero");
+        Self { width }
+    }
+}
+
+impl RecordCodec for FixedWidthCodec {
+    type Record = Vec<u8>;
+
+    fn read<R: BufRead>(&self, reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+        let mut record = vec![0; self.width];
+        let mut filled = 0;
+        while filled < self.width {
+            match reader.read(&mut record[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        match filled {
+            0 => Ok(None),
+            n if n == self.width => Ok(Some(record)),
+            n => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("truncated record: {} of {} bytes", n, self.width),
+            )),
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, record: &Vec<u8>) -> io::Result<()> {
+        if record.len() != self.width {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("record is {} bytes, expected {}", record.len(), self.width),
+            ));
+        }
+        writer.write_all(record)
+    }
+
+    fn size(&self, _record: &Vec<u8>) -> usize {
+        mem::size_of::<Vec<u8>>() + self.width
+    }
+}
+
+/// Out-of-core sort. Stable.
+///
+/// Records are read until the memory budget is used up, sorted with
+/// `MergeSort` and written to a run file in the temp directory. The runs are
+/// then k-way merged through a binary min heap holding the head of every
+/// run. If everything fits in the budget no file is written at all.
+///
+/// At most `fan_in` runs are open at a time. With more runs than that,
+/// neighbouring groups are first merged into longer runs, pass after pass.
+pub struct ExternalSort {
+    memory_budget: usize,
+    temp_dir: PathBuf,
+    fan_in: usize,
+}
+
+impl ExternalSort {
+    pub fn new(memory_budget: usize) -> Self {
+        Self {
+            memory_budget,
+            temp_dir: env::temp_dir(),
+            fan_in: DEFAULT_FAN_IN,
+        }
+    }
+
+    /// Directory for the run files, `std::env::temp_dir()` by default.
+    pub fn temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.temp_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Most runs merged at once, 64 by default. Panics if less than 2.
+    pub fn fan_in(mut self, fan_in: usize) -> Self {
+        assert!(fan_in >= 2, "fan-in must be at least 2");
+        self.fan_in = fan_in;
+        self
+    }
+
+    pub fn sort<C, R, W>(&self, codec: &C, input: R, output: W) -> io::Result<()>
+    where
+        C: RecordCodec,
+        C::Record: Ord,
+        R: Read,
+        W: Write,
+    {
+        self.sort_by(codec, input, output, C::Record::cmp)
+    }
+
+    pub fn sort_by<C, R, W, F>(
+        &self,
+        codec: &C,
+        input: R,
+        output: W,
+        mut compare: F,
+    ) -> io::Result<()>
+    where
+        C: RecordCodec,
+        R: Read,
+        W: Write,
+        F: FnMut(&C::Record, &C::Record) -> Ordering,
+    {
+        let mut input = BufReader::new(input);
+        let mut output = BufWriter::new(output);
+
+        let mut runs = Vec::new();
+        let mut chunk = Vec::new();
+        let mut buf = MergeBuffer::new();
+        let mut done = false;
+        while !done {
+            // always take at least one record, so a tiny budget still makes progress
+            let mut used = 0;
+            while !done && (chunk.is_empty() || used < self.memory_budget) {
+                match codec.read(&mut input)? {
+                    Some(record) => {
+                        used += codec.size(&record);
+                        chunk.push(record);
+                    }
+                    None => done = true,
+                }
+            }
+
+            MergeSort.sort_by_with_buffer(&mut chunk, &mut buf, &mut compare);
+
+            if done && runs.is_empty() {
+                // everything fit in memory
+                for record in &chunk {
+                    codec.write(&mut output, record)?;
+                }
+                return output.flush();
+            }
+
+            if !chunk.is_empty() {
+                runs.push(self.write_run(codec, &chunk)?);
+                chunk.clear();
+            }
+        }
+
+        // merging neighbouring groups keeps the runs in input order, so
+        // the result stays stable
+        while runs.len() > self.fan_in {
+            let mut merged = Vec::with_capacity(runs.len().div_ceil(self.fan_in));
+            while !runs.is_empty() {
+                let group: Vec<RunFile> = runs.drain(..self.fan_in.min(runs.len())).collect();
+                if group.len() == 1 {
+                    merged.extend(group);
+                    continue;
+                }
+
+                let (run, mut writer) = self.create_run()?;
+                merge_runs(codec, &group, &mut writer, &mut compare)?;
+                writer.flush()?;
+                merged.push(run);
+            }
+            runs = merged;
+        }
+
+        merge_runs(codec, &runs, &mut output, &mut compare)?;
+        output.flush()
+    }
+
+    fn write_run<C: RecordCodec>(&self, codec: &C, chunk: &[C::Record]) -> io::Result<RunFile> {
+        let (run, mut writer) = self.create_run()?;
+        for record in chunk {
+            codec.write(&mut writer, record)?;
+        }
+        writer.flush()?;
+
+        Ok(run)
+    }
+
+    fn create_run(&self) -> io::Result<(RunFile, BufWriter<File>)> {
+        let id = RUN_ID.fetch_add(1, AtomicOrdering::Relaxed);
+        let run = RunFile {
+            path: self
+                .temp_dir
+                .join(format!("external-sort-{}-{}.run", process::id(), id)),
+        };
+        let writer = BufWriter::new(File::create(&run.path)?);
+        Ok((run, writer))
+    }
+}
+
+/// Temporary run file, removed when dropped.
+struct RunFile {
+    path: PathBuf,
+}
+
+impl Drop for RunFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn merge_runs<C, W, F>(
+    codec: &C,
+    runs: &[RunFile],
+    output: &mut W,
+    compare: &mut F,
+) -> io::Result<()>
+where
+    C: RecordCodec,
+    W: Write,
+    F: FnMut(&C::Record, &C::Record) -> Ordering,
+{
+    let mut readers = Vec::with_capacity(runs.len());
+    let mut heads = Vec::with_capacity(runs.len());
+    for (idx, run) in runs.iter().enumerate() {
+        let mut reader = BufReader::new(File::open(&run.path)?);
+        if let Some(record) = codec.read(&mut reader)? {
+            heads.push((record, idx));
+        }
+        readers.push(reader);
+    }
+
+    // `heap::sift_down` keeps the largest element on top, so the order is
+    // reversed to get a min heap. Ties go to the earlier run, which keeps
+    // the merge stable.
+    let mut is_less = |a: &(C::Record, usize), b: &(C::Record, usize)| {
+        compare(&b.0, &a.0).then(b.1.cmp(&a.1)) == Ordering::Less
+    };
+    for idx in (0..heads.len() / 2).rev() {
+        heap::sift_down(&mut heads, idx, &mut is_less);
+    }
+
+    while !heads.is_empty() {
+        codec.write(output, &heads[0].0)?;
+
+        let run = heads[0].1;
+        match codec.read(&mut readers[run])? {
+            Some(record) => heads[0].0 = record,
+            None => {
+                heads.swap_remove(0);
+            }
+        }
+        heap::sift_down(&mut heads, 0, &mut is_less);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Distribution;
+    use std::io::Cursor;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("external-sort-test-{}-{}", process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn lines_in_memory() {
+        let input = "pear\napple\nfig\n";
+        let mut output = Vec::new();
+        ExternalSort::new(1 << 20)
+            .sort(&LineCodec, input.as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "apple\nfig\npear\n");
+    }
+
+    #[test]
+    fn lines_over_budget() {
+        let dir = temp_dir("lines");
+        let lines: Vec<String> = Distribution::Random
+            .generate(5000, 1)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let input = lines.join("\n");
+
+        let mut output = Vec::new();
+        ExternalSort::new(2000)
+            .temp_dir(&dir)
+            .sort(&LineCodec, input.as_bytes(), &mut output)
+            .unwrap();
+
+        let mut expected = lines.clone();
+        expected.sort();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+
+        // run files are cleaned up
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn stable_across_runs() {
+        let dir = temp_dir("stable");
+        let lines: Vec<String> = Distribution::Random
+            .generate(3000, 2)
+            .iter()
+            .enumerate()
+            .map(|(i, n)| format!("{}:{}", n % 10, i))
+            .collect();
+        let input = lines.join("\n");
+
+        let key = |s: &String| s.split(':').next().unwrap().to_string();
+        let mut output = Vec::new();
+        ExternalSort::new(1000)
+            .temp_dir(&dir)
+            .sort_by(&LineCodec, input.as_bytes(), &mut output, |a, b| {
+                key(a).cmp(&key(b))
+            })
+            .unwrap();
+
+        let mut expected = lines.clone();
+        expected.sort_by_key(key);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn multiple_merge_passes() {
+        let dir = temp_dir("passes");
+        let lines: Vec<String> = Distribution::Random
+            .generate(2000, 4)
+            .iter()
+            .enumerate()
+            .map(|(i, n)| format!("{}:{}", n % 10, i))
+            .collect();
+        let input = lines.join("\n");
+
+        // about 200 runs of 10 lines, merged 3 at a time
+        let key = |s: &String| s.split(':').next().unwrap().to_string();
+        let mut output = Vec::new();
+        ExternalSort::new(400)
+            .temp_dir(&dir)
+            .fan_in(3)
+            .sort_by(&LineCodec, input.as_bytes(), &mut output, |a, b| {
+                key(a).cmp(&key(b))
+            })
+            .unwrap();
+
+        let mut expected = lines.clone();
+        expected.sort_by_key(key);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn fan_in_of_one() {
+        let _ = ExternalSort::new(1).fan_in(1);
+    }
+
+    #[test]
+    fn fixed_width_descending() {
+        let dir = temp_dir("fixed");
+        let values: Vec<u32> = Distribution::Random
+            .generate(4000, 3)
+            .iter()
+            .map(|&n| n as u32)
+            .collect();
+        let input: Vec<u8> = values.iter().flat_map(|n| n.to_be_bytes()).collect();
+
+        let mut output = Vec::new();
+        ExternalSort::new(500)
+            .temp_dir(&dir)
+            .sort_by(
+                &FixedWidthCodec::new(4),
+                Cursor::new(input),
+                &mut output,
+                |a, b| b.cmp(a),
+            )
+            .unwrap();
+
+        let mut expected = values.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        let sorted: Vec<u32> = output
+            .chunks(4)
+            .map(|c| u32::from_be_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(sorted, expected);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_fixed_width() {
+        let input = vec![1, 2, 3, 4, 5, 6];
+        let err = ExternalSort::new(1 << 20)
+            .sort(&FixedWidthCodec::new(4), Cursor::new(input), Vec::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn empty_input() {
+        let mut output = Vec::new();
+        ExternalSort::new(0)
+            .sort(&LineCodec, io::empty(), &mut output)
+            .unwrap();
+        assert!(output.is_empty());
+    }
+}
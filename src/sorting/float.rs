@@ -0,0 +1,185 @@
+use super::radix::{radix_sort_by_key, RadixKey};
+use super::SortError;
+
+/// Where a float sort puts NaN values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// All NaNs before every number, whatever their sign bit.
+    First,
+    /// All NaNs after every number, whatever their sign bit.
+    Last,
+    /// Refuse the input with `SortError::NaN`.
+    Error,
+}
+
+/// `f32` or `f64`.
+///
+/// `total_key` maps the value to a signed integer whose order is the IEEE 754
+/// totalOrder: `-NaN < -inf < ... < -0.0 < +0.0 < ... < +inf < +NaN`.
+pub trait Float: Copy {
+    type Key: RadixKey + Ord;
+
+    fn is_nan(self) -> bool;
+
+    fn total_key(self) -> Self::Key;
+
+    /// The same value with the sign bit set or cleared.
+    fn with_sign(self, negative: bool) -> Self;
+}
+
+macro_rules! float {
+    ($($t:ty => $i:ty),*) => {$(
+        impl Float for $t {
+            type Key = $i;
+
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+
+            fn total_key(self) -> $i {
+                // negative values count down from the sign bit, so flip the rest
+                let bits = self.to_bits() as $i;
+                bits ^ ((bits >> (<$i>::BITS - 1)) & <$i>::MAX)
+            }
+
+            fn with_sign(self, negative: bool) -> $t {
+                let sign = 1 << (<$i>::BITS - 1);
+                if negative {
+                    <$t>::from_bits(self.to_bits() | sign)
+                } else {
+                    <$t>::from_bits(self.to_bits() & !sign)
+                }
+            }
+        }
+    )*};
+}
+
+float!(f32 => i32, f64 => i64);
+
+/// Sorts floats in IEEE total order, -0.0 before +0.0, with NaNs placed by
+/// `nan`. Stable, radix sort on the bit pattern.
+///
+/// With `NanPolicy::Error` the first NaN is reported and `arr` is left
+/// unchanged.
+pub fn float_sort<T: Float>(arr: &mut [T], nan: NanPolicy) -> Result<(), SortError> {
+    float_sort_by_key(arr, nan, |&x| x)
+}
+
+/// Sorts records by a float key, see `float_sort`. Stable.
+pub fn float_sort_by_key<T, K, F>(arr: &mut [T], nan: NanPolicy, mut f: F) -> Result<(), SortError>
+where
+    K: Float,
+    F: FnMut(&T) -> K,
+{
+    if nan == NanPolicy::Error {
+        if let Some(index) = arr.iter().position(|x| f(x).is_nan()) {
+            return Err(SortError::NaN { index });
+        }
+    }
+
+    // a NaN with the sign bit set is below -inf, without it above +inf
+    radix_sort_by_key(arr, |x| {
+        let key = f(x);
+        if key.is_nan() {
+            key.with_sign(nan == NanPolicy::First).total_key()
+        } else {
+            key.total_key()
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits<T: Float>(arr: &[T]) -> Vec<T::Key> {
+        arr.iter().map(|x| x.total_key()).collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![3.5, -1.0, 0.0, 2.25, -7.5, 1e300, f64::NEG_INFINITY];
+        float_sort(&mut arr, NanPolicy::Error).unwrap();
+        assert_eq!(
+            arr,
+            vec![f64::NEG_INFINITY, -7.5, -1.0, 0.0, 2.25, 3.5, 1e300]
+        );
+    }
+
+    #[test]
+    fn signed_zero() {
+        let mut arr = vec![0.0f32, -0.0, 0.0, -0.0];
+        float_sort(&mut arr, NanPolicy::Error).unwrap();
+        assert_eq!(bits(&arr), bits(&[-0.0f32, -0.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn matches_total_cmp() {
+        let mut arr = vec![
+            1.0,
+            -0.0,
+            f64::MIN_POSITIVE,
+            -f64::MIN_POSITIVE,
+            f64::INFINITY,
+            0.0,
+            -5e-324,
+            5e-324,
+            f64::MAX,
+            f64::MIN,
+            f64::NEG_INFINITY,
+        ];
+        let mut expected = arr.clone();
+        expected.sort_by(f64::total_cmp);
+
+        float_sort(&mut arr, NanPolicy::Last).unwrap();
+        assert_eq!(bits(&arr), bits(&expected));
+    }
+
+    #[test]
+    fn nan_first_and_last() {
+        let neg_nan = -f32::NAN;
+        let arr = vec![2.0f32, f32::NAN, -1.0, neg_nan, f32::INFINITY];
+
+        let mut first = arr.clone();
+        float_sort(&mut first, NanPolicy::First).unwrap();
+        assert!(first[0].is_nan() && first[1].is_nan());
+        assert_eq!(&first[2..], &[-1.0, 2.0, f32::INFINITY]);
+
+        let mut last = arr.clone();
+        float_sort(&mut last, NanPolicy::Last).unwrap();
+        assert_eq!(&last[..3], &[-1.0, 2.0, f32::INFINITY]);
+        assert!(last[3].is_nan() && last[4].is_nan());
+
+        // the NaNs keep their sign and payload
+        assert_eq!(
+            first.iter().filter(|x| x.is_sign_negative()).count(),
+            last.iter().filter(|x| x.is_sign_negative()).count()
+        );
+    }
+
+    #[test]
+    fn nan_error() {
+        let mut arr = vec![1.0, 0.5, f64::NAN, 0.25, f64::NAN];
+        let before = bits(&arr);
+        assert_eq!(
+            float_sort(&mut arr, NanPolicy::Error),
+            Err(SortError::NaN { index: 2 })
+        );
+        assert_eq!(bits(&arr), before);
+    }
+
+    #[test]
+    fn stable_records() {
+        let mut arr = vec![
+            ("a", 0.5),
+            ("b", f64::NAN),
+            ("c", -0.0),
+            ("d", 0.5),
+            ("e", 0.0),
+        ];
+        float_sort_by_key(&mut arr, NanPolicy::First, |&(_, x)| x).unwrap();
+        let names: Vec<_> = arr.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, vec!["b", "c", "e", "a", "d"]);
+    }
+}
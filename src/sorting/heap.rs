@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::Sorter;
+
+/// In-place heap sort. Not stable, O(n log n) in every case.
+///
+/// Same layout as `data_structures::heap::Heap`: children of `idx` live at
+/// `2 * idx + 1` and `2 * idx + 2`. A max heap is built over the whole slice
+/// and the root is swapped to the back until the heap is empty.
+pub struct HeapSort;
+
+impl Sorter for HeapSort {
+    const STABLE: bool = false;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_observed(arr, compare, &mut ());
+    }
+}
+
+impl ObservedSorter for HeapSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        sort_observed(
+            arr,
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(observer),
+        );
+    }
+}
+
+pub fn heap_sort<T: Ord>(arr: &mut [T]) {
+    HeapSort.sort(arr);
+}
+
+pub fn heap_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    HeapSort.sort_by(arr, compare);
+}
+
+pub fn heap_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    HeapSort.sort_by_key(arr, f);
+}
+
+pub(crate) fn sort<T, F>(v: &mut [T], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    sort_observed(v, is_less, &mut Trace::new(&mut ()));
+}
+
+pub(crate) fn sort_observed<T, F, O>(v: &mut [T], is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+
+    // heapify, bottom-up from the last parent
+    for idx in (0..len / 2).rev() {
+        sift_down_observed(v, idx, is_less, trace);
+    }
+
+    for end in (1..len).rev() {
+        v.swap(0, end);
+        trace.swap(0, end);
+        sift_down_observed(&mut v[..end], 0, is_less, trace);
+    }
+}
+
+pub(crate) fn sift_down<T, F>(v: &mut [T], idx: usize, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    sift_down_observed(v, idx, is_less, &mut Trace::new(&mut ()));
+}
+
+pub(crate) fn sift_up<T, F>(v: &mut [T], mut idx: usize, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    while idx > 0 {
+        let pdx = (idx - 1) / 2;
+        if !is_less(&v[pdx], &v[idx]) {
+            break;
+        }
+        v.swap(idx, pdx);
+        idx = pdx;
+    }
+}
+
+fn sift_down_observed<T, F, O>(v: &mut [T], mut idx: usize, is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    loop {
+        let ldx = 2 * idx + 1;
+        if ldx >= v.len() {
+            break;
+        }
+
+        let rdx = ldx + 1;
+        let mut cdx = ldx;
+        if rdx < v.len() {
+            trace.compare(ldx, rdx);
+            if is_less(&v[ldx], &v[rdx]) {
+                cdx = rdx;
+            }
+        }
+
+        trace.compare(idx, cdx);
+        if !is_less(&v[idx], &v[cdx]) {
+            break;
+        }
+        v.swap(idx, cdx);
+        trace.swap(idx, cdx);
+        idx = cdx;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        heap_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let mut arr: Vec<i32> = vec![];
+        heap_sort(&mut arr);
+        assert!(arr.is_empty());
+
+        let mut arr = vec![1];
+        heap_sort(&mut arr);
+        assert_eq!(arr, vec![1]);
+    }
+
+    #[test]
+    fn descending_by_key() {
+        let mut arr = vec![(1, "a"), (3, "c"), (2, "b"), (3, "d")];
+        heap_sort_by(&mut arr, |a, b| b.cmp(a));
+        assert_eq!(arr, vec![(3, "d"), (3, "c"), (2, "b"), (1, "a")]);
+
+        heap_sort_by_key(&mut arr, |&(n, _)| n);
+        assert_eq!(
+            arr.iter().map(|&(n, _)| n).collect::<Vec<_>>(),
+            vec![1, 2, 3, 3]
+        );
+    }
+}
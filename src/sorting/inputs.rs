@@ -0,0 +1,123 @@
+/// Small seeded random number generator (SplitMix64), so test and benchmark
+/// inputs are the same on every run.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`, `n` must not be zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+/// Shapes of input data for tests and benchmarks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    Random,
+    Sorted,
+    Reversed,
+    AllEqual,
+    /// Ascending runs that restart from 0, about eight of them.
+    Sawtooth,
+    /// Random values out of eight distinct ones.
+    FewUnique,
+    /// Ascending up to the middle, then descending.
+    OrganPipe,
+}
+
+impl Distribution {
+    pub const ALL: [Distribution; 7] = [
+        Distribution::Random,
+        Distribution::Sorted,
+        Distribution::Reversed,
+        Distribution::AllEqual,
+        Distribution::Sawtooth,
+        Distribution::FewUnique,
+        Distribution::OrganPipe,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Distribution::Random => "random",
+            Distribution::Sorted => "sorted",
+            Distribution::Reversed => "reversed",
+            Distribution::AllEqual => "all-equal",
+            Distribution::Sawtooth => "sawtooth",
+            Distribution::FewUnique => "few-unique",
+            Distribution::OrganPipe => "organ-pipe",
+        }
+    }
+
+    pub fn generate(self, len: usize, seed: u64) -> Vec<u64> {
+        let mut rng = Rng::new(seed);
+        let n = len as u64;
+        (0..n)
+            .map(|i| match self {
+                Distribution::Random => rng.next_u64(),
+                Distribution::Sorted => i,
+                Distribution::Reversed => n - i,
+                Distribution::AllEqual => 42,
+                Distribution::Sawtooth => i % (n / 8).max(1),
+                Distribution::FewUnique => rng.below(8),
+                Distribution::OrganPipe => i.min(n - i),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded() {
+        assert_eq!(
+            Distribution::Random.generate(100, 7),
+            Distribution::Random.generate(100, 7)
+        );
+        assert_ne!(
+            Distribution::Random.generate(100, 7),
+            Distribution::Random.generate(100, 8)
+        );
+    }
+
+    #[test]
+    fn shapes() {
+        assert_eq!(Distribution::Sorted.generate(4, 0), vec![0, 1, 2, 3]);
+        assert_eq!(Distribution::Reversed.generate(4, 0), vec![4, 3, 2, 1]);
+        assert_eq!(
+            Distribution::OrganPipe.generate(6, 0),
+            vec![0, 1, 2, 3, 2, 1]
+        );
+        assert_eq!(
+            Distribution::Sawtooth.generate(16, 0),
+            vec![0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1]
+        );
+        assert!(Distribution::FewUnique
+            .generate(1000, 0)
+            .iter()
+            .all(|&x| x < 8));
+        assert!(Distribution::ALL
+            .iter()
+            .all(|d| d.generate(0, 0).is_empty()));
+    }
+
+    #[test]
+    fn below_bound() {
+        let mut rng = Rng::new(1);
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+    }
+}
@@ -0,0 +1,97 @@
+use std::cmp::Ordering;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::Sorter;
+
+/// Insertion sort. Stable, O(n) on sorted input, used by the other sorts for
+/// small slices.
+pub struct InsertionSort;
+
+impl Sorter for InsertionSort {
+    const STABLE: bool = true;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_observed(arr, compare, &mut ());
+    }
+}
+
+impl ObservedSorter for InsertionSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        sort_observed(
+            arr,
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(observer),
+        );
+    }
+}
+
+pub fn insertion_sort<T: Ord>(arr: &mut [T]) {
+    InsertionSort.sort(arr);
+}
+
+pub fn insertion_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    InsertionSort.sort_by(arr, compare);
+}
+
+pub fn insertion_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    InsertionSort.sort_by_key(arr, f);
+}
+
+pub(crate) fn sort<T, F>(v: &mut [T], is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    sort_observed(v, is_less, &mut Trace::new(&mut ()));
+}
+
+pub(crate) fn sort_observed<T, F, O>(v: &mut [T], is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 {
+            trace.compare(j, j - 1);
+            if !is_less(&v[j], &v[j - 1]) {
+                break;
+            }
+            v.swap(j, j - 1);
+            trace.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        insertion_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn stable() {
+        let mut arr = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        insertion_sort_by_key(&mut arr, |&(k, _)| k);
+        assert_eq!(arr, vec![(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+    }
+}
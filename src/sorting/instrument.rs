@@ -0,0 +1,303 @@
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+use super::Sorter;
+
+/// A single step of a sort. Indices are positions in the sorted slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Compare(usize, usize),
+    Swap(usize, usize),
+    /// An element was written to this position (merge sort copies).
+    Write(usize),
+}
+
+/// Receives every `Event` of an instrumented sort.
+///
+/// Implemented by `()` (ignores everything), `Counters`, `JsonLines` and by
+/// any `FnMut(Event)` closure.
+pub trait Observer {
+    fn event(&mut self, event: Event);
+}
+
+impl Observer for () {
+    fn event(&mut self, _event: Event) {}
+}
+
+impl<F: FnMut(Event)> Observer for F {
+    fn event(&mut self, event: Event) {
+        self(event)
+    }
+}
+
+/// Operation counts. A swap also counts as two writes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+    pub comparisons: usize,
+    pub swaps: usize,
+    pub writes: usize,
+}
+
+impl Observer for Counters {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Compare(..) => self.comparisons += 1,
+            Event::Swap(..) => {
+                self.swaps += 1;
+                self.writes += 2;
+            }
+            Event::Write(..) => self.writes += 1,
+        }
+    }
+}
+
+/// Writes every event as one JSON object per line, e.g.
+/// `{"op":"swap","i":3,"j":4}`.
+///
+/// The first I/O error stops the trace and is returned by `finish`.
+pub struct JsonLines<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => {
+                self.writer.flush()?;
+                Ok(self.writer)
+            }
+        }
+    }
+}
+
+impl<W: Write> Observer for JsonLines<W> {
+    fn event(&mut self, event: Event) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let result = match event {
+            Event::Compare(i, j) => {
+                writeln!(self.writer, r#"{{"op":"compare","i":{},"j":{}}}"#, i, j)
+            }
+            Event::Swap(i, j) => writeln!(self.writer, r#"{{"op":"swap","i":{},"j":{}}}"#, i, j),
+            Event::Write(i) => writeln!(self.writer, r#"{{"op":"write","i":{}}}"#, i),
+        };
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+}
+
+/// A sorter that can report its steps to an `Observer`.
+///
+/// The plain `Sorter::sort_by` of these sorters runs the same code with `()`
+/// as observer, so the instrumented run does exactly the same work.
+pub trait ObservedSorter: Sorter {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer;
+
+    fn sort_observed<T, O>(&self, arr: &mut [T], observer: &mut O)
+    where
+        T: Ord,
+        O: Observer,
+    {
+        self.sort_by_observed(arr, T::cmp, observer);
+    }
+
+    /// Sorts `arr` and returns how many operations it took.
+    fn count<T: Ord>(&self, arr: &mut [T]) -> Counters {
+        let mut counters = Counters::default();
+        self.sort_observed(arr, &mut counters);
+        counters
+    }
+}
+
+/// Observer plus the position of the current sub-slice in the whole slice,
+/// so recursive sorts can report absolute indices.
+pub(crate) struct Trace<'a, O: Observer> {
+    observer: &'a mut O,
+    offset: usize,
+}
+
+impl<'a, O: Observer> Trace<'a, O> {
+    pub(crate) fn new(observer: &'a mut O) -> Self {
+        Self {
+            observer,
+            offset: 0,
+        }
+    }
+
+    /// Trace for the sub-slice starting at `offset`.
+    pub(crate) fn at(&mut self, offset: usize) -> Trace<'_, O> {
+        Trace {
+            observer: self.observer,
+            offset: self.offset + offset,
+        }
+    }
+
+    pub(crate) fn compare(&mut self, i: usize, j: usize) {
+        self.observer
+            .event(Event::Compare(self.offset + i, self.offset + j));
+    }
+
+    pub(crate) fn swap(&mut self, i: usize, j: usize) {
+        self.observer
+            .event(Event::Swap(self.offset + i, self.offset + j));
+    }
+
+    pub(crate) fn write(&mut self, i: usize) {
+        self.observer.event(Event::Write(self.offset + i));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{
+        BottomUpMergeSort, BubbleSort, HeapSort, InsertionSort, MergeSort, QuickSort, TimSort,
+    };
+
+    #[test]
+    fn bubble_counts() {
+        let mut arr = vec![3, 2, 1];
+        let counters = BubbleSort.count(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3]);
+        assert_eq!(
+            counters,
+            Counters {
+                comparisons: 3,
+                swaps: 3,
+                writes: 6,
+            }
+        );
+
+        // sorted input exits after one pass
+        let mut arr: Vec<i32> = (0..100).collect();
+        let counters = BubbleSort.count(&mut arr);
+        assert_eq!(counters.comparisons, 99);
+        assert_eq!(counters.swaps, 0);
+    }
+
+    #[test]
+    fn bubble_trace() {
+        let mut events = Vec::new();
+        let mut arr = vec![2, 1, 3];
+        BubbleSort.sort_observed(&mut arr, &mut |e| events.push(e));
+        assert_eq!(
+            events,
+            vec![
+                Event::Compare(0, 1),
+                Event::Swap(0, 1),
+                Event::Compare(1, 2),
+                Event::Compare(0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let mut trace = JsonLines::new(Vec::new());
+        let mut arr = vec![2, 1];
+        BubbleSort.sort_observed(&mut arr, &mut trace);
+
+        let output = String::from_utf8(trace.finish().unwrap()).unwrap();
+        assert_eq!(
+            output,
+            "{\"op\":\"compare\",\"i\":0,\"j\":1}\n{\"op\":\"swap\",\"i\":0,\"j\":1}\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_error() {
+        struct Broken;
+
+        impl Write for Broken {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut trace = JsonLines::new(Broken);
+        BubbleSort.sort_observed(&mut [2, 1], &mut trace);
+        assert!(trace.finish().is_err());
+    }
+
+    // replaying the trace on a copy of the input has to give the same result
+    // as the sort itself, and every index has to stay in bounds
+    fn replay<S: ObservedSorter>(sorter: &S) {
+        let input: Vec<u32> = (0..300).map(|i| (i * 7919) % 211).collect();
+        let mut arr = input.clone();
+        let mut replayed = input.clone();
+        let mut counters = Counters::default();
+
+        sorter.sort_observed(&mut arr, &mut |e| {
+            counters.event(e);
+            match e {
+                Event::Compare(i, j) => assert!(i < input.len() && j < input.len()),
+                Event::Swap(i, j) => replayed.swap(i, j),
+                Event::Write(i) => assert!(i < input.len()),
+            }
+        });
+
+        let mut expected = input.clone();
+        expected.sort();
+        assert_eq!(arr, expected);
+        assert!(counters.comparisons > 0);
+        if counters.swaps * 2 == counters.writes {
+            assert_eq!(replayed, expected);
+        }
+    }
+
+    #[test]
+    fn every_sorter() {
+        replay(&BubbleSort);
+        replay(&InsertionSort);
+        replay(&HeapSort);
+        replay(&QuickSort);
+        replay(&MergeSort);
+        replay(&BottomUpMergeSort);
+        replay(&TimSort);
+    }
+
+    #[test]
+    fn comparisons_match_plain_sort() {
+        let input: Vec<u32> = (0..500).map(|i| (i * 7919) % 499).collect();
+
+        let mut plain = 0;
+        QuickSort.sort_by(&mut input.clone(), |a, b| {
+            plain += 1;
+            a.cmp(b)
+        });
+        assert_eq!(QuickSort.count(&mut input.clone()).comparisons, plain);
+
+        let mut plain = 0;
+        MergeSort.sort_by(&mut input.clone(), |a, b| {
+            plain += 1;
+            a.cmp(b)
+        });
+        assert_eq!(MergeSort.count(&mut input.clone()).comparisons, plain);
+
+        let mut plain = 0;
+        TimSort.sort_by(&mut input.clone(), |a, b| {
+            plain += 1;
+            a.cmp(b)
+        });
+        assert_eq!(TimSort.count(&mut input.clone()).comparisons, plain);
+    }
+}
@@ -0,0 +1,172 @@
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+
+use super::heap;
+use super::select::CompareFn;
+
+/// Yields the elements of a `Vec` in sorted order, sorting only as far as it
+/// is consumed. Not stable.
+///
+/// `new` heapifies in O(n) and every `next` pops the heap in O(log n), so
+/// taking the first `k` elements costs O(n + k log n) instead of a full
+/// sort.
+pub struct SortedIter<T, F> {
+    // min heap with respect to `compare`, same layout as in `heap_sort`
+    heap: Vec<T>,
+    compare: F,
+}
+
+impl<T: Ord> SortedIter<T, CompareFn<T>> {
+    pub fn new(v: Vec<T>) -> Self {
+        Self::new_by(v, T::cmp)
+    }
+}
+
+impl<T, F> SortedIter<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub fn new_by(mut v: Vec<T>, mut compare: F) -> Self {
+        let mut is_greater = |a: &T, b: &T| compare(b, a) == Ordering::Less;
+        for idx in (0..v.len() / 2).rev() {
+            heap::sift_down(&mut v, idx, &mut is_greater);
+        }
+        Self { heap: v, compare }
+    }
+
+    /// The next element without taking it, O(1).
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.first()
+    }
+
+    /// The elements not yielded yet, in no particular order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.heap
+    }
+}
+
+impl<T, F> Iterator for SortedIter<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let last = self.heap.len().checked_sub(1)?;
+        self.heap.swap(0, last);
+        let min = self.heap.pop();
+
+        let compare = &mut self.compare;
+        heap::sift_down(&mut self.heap, 0, &mut |a, b| {
+            compare(b, a) == Ordering::Less
+        });
+        min
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T, F> ExactSizeIterator for SortedIter<T, F> where F: FnMut(&T, &T) -> Ordering {}
+
+impl<T, F> FusedIterator for SortedIter<T, F> where F: FnMut(&T, &T) -> Ordering {}
+
+pub fn sorted_iter<T: Ord>(v: Vec<T>) -> SortedIter<T, CompareFn<T>> {
+    SortedIter::new(v)
+}
+
+pub fn sorted_iter_by<T, F>(v: Vec<T>, compare: F) -> SortedIter<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    SortedIter::new_by(v, compare)
+}
+
+pub fn sorted_iter_by_key<T, K, F>(
+    v: Vec<T>,
+    mut f: F,
+) -> SortedIter<T, impl FnMut(&T, &T) -> Ordering>
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    SortedIter::new_by(v, move |a, b| f(a).cmp(&f(b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Distribution;
+
+    #[test]
+    fn test_sorted_iter() {
+        let v = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        assert_eq!(
+            sorted_iter(v).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8]
+        );
+
+        let mut empty = sorted_iter(Vec::<i32>::new());
+        assert_eq!(empty.next(), None);
+        assert_eq!(empty.next(), None);
+    }
+
+    #[test]
+    fn every_distribution() {
+        for distribution in Distribution::ALL {
+            for len in [0, 1, 2, 3, 100, 1000] {
+                let v = distribution.generate(len, 11);
+                let mut expected = v.clone();
+                expected.sort();
+                assert_eq!(sorted_iter(v).collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn by_comparator_and_key() {
+        let v = vec![(1, "a"), (3, "c"), (2, "b"), (4, "d")];
+        let desc: Vec<_> = sorted_iter_by(v.clone(), |a, b| b.cmp(a)).collect();
+        assert_eq!(desc, vec![(4, "d"), (3, "c"), (2, "b"), (1, "a")]);
+
+        let by_name: Vec<_> = sorted_iter_by_key(v, |&(_, name)| name)
+            .map(|(n, _)| n)
+            .collect();
+        assert_eq!(by_name, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn peek_and_len() {
+        let mut iter = sorted_iter(vec![3, 1, 2]);
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.peek(), Some(&1));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.len(), 2);
+
+        let mut rest = iter.into_vec();
+        rest.sort();
+        assert_eq!(rest, vec![2, 3]);
+    }
+
+    #[test]
+    fn first_k_is_cheap() {
+        let n = 100_000;
+        let k = 10;
+        let v = Distribution::Random.generate(n, 5);
+        let mut expected = v.clone();
+        expected.sort();
+
+        let mut comparisons = 0;
+        let first: Vec<u64> = sorted_iter_by(v, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        })
+        .take(k)
+        .collect();
+
+        assert_eq!(first, expected[..k]);
+        // heapify takes at most 2n, each pop 2 log2(n)
+        assert!(comparisons <= 2 * n + k * 2 * n.ilog2() as usize);
+    }
+}
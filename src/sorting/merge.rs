@@ -0,0 +1,424 @@
+use std::cmp::Ordering;
+use std::ptr;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::Sorter;
+
+/// Reusable scratch space for the merge step.
+///
+/// The buffer never holds live elements between sorts, it only keeps its
+/// allocation around so repeated sorts of similar size don't allocate again.
+pub struct MergeBuffer<T> {
+    buf: Vec<T>,
+}
+
+impl<T> MergeBuffer<T> {
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+}
+
+impl<T> Default for MergeBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Top-down (recursive) merge sort. Stable.
+pub struct MergeSort;
+
+/// Bottom-up (iterative) merge sort. Stable.
+pub struct BottomUpMergeSort;
+
+impl MergeSort {
+    pub fn sort_by_with_buffer<T, F>(&self, arr: &mut [T], buf: &mut MergeBuffer<T>, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        top_down(arr, &mut buf.buf, &mut |a, b| {
+            compare(a, b) == Ordering::Less
+        });
+    }
+}
+
+impl BottomUpMergeSort {
+    pub fn sort_by_with_buffer<T, F>(&self, arr: &mut [T], buf: &mut MergeBuffer<T>, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        bottom_up(
+            arr,
+            &mut buf.buf,
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(&mut ()),
+        );
+    }
+}
+
+impl Sorter for MergeSort {
+    const STABLE: bool = true;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_with_buffer(arr, &mut MergeBuffer::new(), compare);
+    }
+}
+
+impl Sorter for BottomUpMergeSort {
+    const STABLE: bool = true;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_with_buffer(arr, &mut MergeBuffer::new(), compare);
+    }
+}
+
+impl ObservedSorter for MergeSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        top_down_observed(
+            arr,
+            &mut Vec::new(),
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(observer),
+        );
+    }
+}
+
+impl ObservedSorter for BottomUpMergeSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        bottom_up(
+            arr,
+            &mut Vec::new(),
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(observer),
+        );
+    }
+}
+
+pub fn merge_sort<T: Ord>(arr: &mut [T]) {
+    MergeSort.sort(arr);
+}
+
+pub fn merge_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    MergeSort.sort_by(arr, compare);
+}
+
+pub fn merge_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    MergeSort.sort_by_key(arr, f);
+}
+
+pub fn bottom_up_merge_sort<T: Ord>(arr: &mut [T]) {
+    BottomUpMergeSort.sort(arr);
+}
+
+pub fn bottom_up_merge_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    BottomUpMergeSort.sort_by(arr, compare);
+}
+
+pub fn bottom_up_merge_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    BottomUpMergeSort.sort_by_key(arr, f);
+}
+
+pub(crate) fn top_down<T, F>(v: &mut [T], buf: &mut Vec<T>, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    top_down_observed(v, buf, is_less, &mut Trace::new(&mut ()));
+}
+
+fn top_down_observed<T, F, O>(v: &mut [T], buf: &mut Vec<T>, is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    if len <= 1 {
+        return;
+    }
+
+    let mid = len / 2;
+    top_down_observed(&mut v[..mid], buf, is_less, trace);
+    top_down_observed(&mut v[mid..], buf, is_less, &mut trace.at(mid));
+    merge_observed(v, mid, buf, is_less, trace);
+}
+
+fn bottom_up<T, F, O>(v: &mut [T], buf: &mut Vec<T>, is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    let mut width = 1;
+
+    while width < len {
+        let mut start = 0;
+        while start + width < len {
+            let end = (start + 2 * width).min(len);
+            merge_observed(
+                &mut v[start..end],
+                width,
+                buf,
+                is_less,
+                &mut trace.at(start),
+            );
+            start = end;
+        }
+
+        width *= 2;
+    }
+}
+
+/// Merges the sorted runs `v[..mid]` and `v[mid..]` in place, using `buf` as
+/// scratch space for the left run. Equal elements keep their order.
+pub(crate) fn merge<T, F>(v: &mut [T], mid: usize, buf: &mut Vec<T>, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    merge_observed(v, mid, buf, is_less, &mut Trace::new(&mut ()));
+}
+
+// Compare events name the position an element of the left run had before it
+// was moved to `buf`, writes are only reported for `v`, not for `buf`.
+fn merge_observed<T, F, O>(
+    v: &mut [T],
+    mid: usize,
+    buf: &mut Vec<T>,
+    is_less: &mut F,
+    trace: &mut Trace<O>,
+) where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    if mid == 0 || mid >= len {
+        return;
+    }
+
+    // already in order, nothing to do
+    trace.compare(mid, mid - 1);
+    if !is_less(&v[mid], &v[mid - 1]) {
+        return;
+    }
+
+    debug_assert!(buf.is_empty());
+    buf.reserve(mid);
+
+    // SAFETY: the left run is moved into `buf` and the hole left behind in
+    // `v` is always exactly as large as what is still in `buf`. `MergeHole`
+    // copies the rest of `buf` back on drop, so every element ends up in `v`
+    // exactly once even if `is_less` panics.
+    unsafe {
+        let v = v.as_mut_ptr();
+        let b = buf.as_mut_ptr();
+        ptr::copy_nonoverlapping(v, b, mid);
+
+        let mut hole = MergeHole {
+            start: b,
+            end: b.add(mid),
+            dest: v,
+        };
+        let mut right = v.add(mid);
+        let right_end = v.add(len);
+
+        while hole.start < hole.end && right < right_end {
+            let l = hole.start.offset_from(b) as usize;
+            let r = right.offset_from(v) as usize;
+            trace.compare(r, l);
+            trace.write(hole.dest.offset_from(v) as usize);
+
+            // take from the right run only if strictly smaller, that keeps it stable
+            if is_less(&*right, &*hole.start) {
+                ptr::copy_nonoverlapping(right, hole.dest, 1);
+                right = right.add(1);
+            } else {
+                ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                hole.start = hole.start.add(1);
+            }
+            hole.dest = hole.dest.add(1);
+        }
+
+        // whatever is left in `buf` is copied back by `hole` when it drops
+        let dest = hole.dest.offset_from(v) as usize;
+        for i in 0..hole.end.offset_from(hole.start) as usize {
+            trace.write(dest + i);
+        }
+    }
+}
+
+/// Elements `start..end` of the scratch buffer that still have to go back
+/// to `dest`. Copies them on drop.
+pub(crate) struct MergeHole<T> {
+    pub(crate) start: *mut T,
+    pub(crate) end: *mut T,
+    pub(crate) dest: *mut T,
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Distribution;
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        merge_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        bottom_up_merge_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let mut arr: Vec<i32> = vec![];
+        merge_sort(&mut arr);
+        bottom_up_merge_sort(&mut arr);
+        assert!(arr.is_empty());
+
+        let mut arr = vec![1];
+        merge_sort(&mut arr);
+        bottom_up_merge_sort(&mut arr);
+        assert_eq!(arr, vec![1]);
+    }
+
+    #[test]
+    fn random_input() {
+        for len in [2, 3, 10, 100, 1000, 1023, 1025] {
+            let arr = Distribution::Random.generate(len, len as u64);
+            let mut expected = arr.clone();
+            expected.sort();
+
+            let mut top = arr.clone();
+            merge_sort(&mut top);
+            assert_eq!(top, expected);
+
+            let mut bottom = arr.clone();
+            bottom_up_merge_sort(&mut bottom);
+            assert_eq!(bottom, expected);
+        }
+    }
+
+    #[test]
+    fn stable_with_duplicate_keys() {
+        let keys = Distribution::Random.generate(5000, 7);
+        let records: Vec<(u64, usize)> = keys.iter().map(|k| k % 10).zip(0..).collect();
+
+        let mut expected = records.clone();
+        expected.sort_by_key(|&(k, _)| k);
+
+        let mut top = records.clone();
+        merge_sort_by_key(&mut top, |&(k, _)| k);
+        assert_eq!(top, expected);
+
+        let mut bottom = records.clone();
+        bottom_up_merge_sort_by_key(&mut bottom, |&(k, _)| k);
+        assert_eq!(bottom, expected);
+    }
+
+    #[test]
+    fn descending() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        merge_sort_by(&mut arr, |a, b| b.cmp(a));
+        assert_eq!(arr, vec![8, 7, 6, 5, 4, 3, 2, 1]);
+
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        bottom_up_merge_sort_by(&mut arr, |a, b| b.cmp(a));
+        assert_eq!(arr, vec![8, 7, 6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn reuse_buffer() {
+        let mut buf = MergeBuffer::with_capacity(1000);
+        let capacity = buf.capacity();
+
+        for seed in 0..10 {
+            let mut arr: Vec<String> = Distribution::Random
+                .generate(1000, seed)
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            let mut expected = arr.clone();
+            expected.sort();
+
+            if seed % 2 == 0 {
+                MergeSort.sort_by_with_buffer(&mut arr, &mut buf, |a, b| a.cmp(b));
+            } else {
+                BottomUpMergeSort.sort_by_with_buffer(&mut arr, &mut buf, |a, b| a.cmp(b));
+            }
+            assert_eq!(arr, expected);
+            assert_eq!(buf.capacity(), capacity);
+        }
+    }
+
+    #[test]
+    fn panic_in_compare_keeps_elements() {
+        let mut arr: Vec<String> = Distribution::Random
+            .generate(100, 3)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let mut calls = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            merge_sort_by(&mut arr, |a, b| {
+                calls += 1;
+                if calls == 300 {
+                    panic!("compare");
+                }
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err());
+
+        arr.sort();
+        assert_eq!(arr, expected);
+    }
+}
@@ -1 +1,95 @@
+use std::cmp::Ordering;
+
 mod bubble;
+mod bucket;
+#[cfg(test)]
+mod conformance;
+mod counting;
+mod error;
+mod external;
+mod float;
+mod heap;
+mod inputs;
+mod insertion;
+mod instrument;
+mod lazy;
+mod merge;
+mod natural;
+mod parallel;
+mod presorted;
+mod quick;
+mod radix;
+mod select;
+mod setops;
+mod shell;
+mod spec;
+mod tim;
+
+pub use bubble::{bubble_sort, bubble_sort_by, bubble_sort_by_key, BubbleSort};
+pub use bucket::{bucket_sort, bucket_sort_by_key};
+pub use counting::{counting_sort, counting_sort_by_key};
+pub use error::SortError;
+pub use external::{ExternalSort, FixedWidthCodec, LineCodec, RecordCodec};
+pub use float::{float_sort, float_sort_by_key, Float, NanPolicy};
+pub use heap::{heap_sort, heap_sort_by, heap_sort_by_key, HeapSort};
+#[cfg(test)]
+pub(crate) use inputs::{Distribution, Rng};
+pub use insertion::{insertion_sort, insertion_sort_by, insertion_sort_by_key, InsertionSort};
+pub use instrument::{Counters, Event, JsonLines, ObservedSorter, Observer};
+pub use lazy::{sorted_iter, sorted_iter_by, sorted_iter_by_key, SortedIter};
+pub use merge::{
+    bottom_up_merge_sort, bottom_up_merge_sort_by, bottom_up_merge_sort_by_key, merge_sort,
+    merge_sort_by, merge_sort_by_key, BottomUpMergeSort, MergeBuffer, MergeSort,
+};
+pub use natural::{natural_cmp, natural_cmp_ignore_case};
+pub use parallel::{par_merge_sort, par_merge_sort_by, par_merge_sort_by_key, ParallelMergeSort};
+pub use presorted::{
+    inversions, inversions_by, is_sorted, is_sorted_by, lis_len, lis_len_by, presortedness,
+    presortedness_by, runs, runs_by, Presortedness,
+};
+pub use quick::{quick_sort, quick_sort_by, quick_sort_by_key, QuickSort};
+pub use radix::{msd_radix_sort, msd_radix_sort_by_key, radix_sort, radix_sort_by_key, RadixKey};
+pub use select::{
+    partial_sort, partial_sort_by, partial_sort_by_key, select_nth, select_nth_by,
+    select_nth_by_key, TopK, TopKExt, TopKIter,
+};
+pub use setops::{
+    Dedup, Difference, Intersection, Merge, SortedIterExt, SortedVecExt, SymmetricDifference, Union,
+};
+pub use shell::{shell_sort, shell_sort_by, shell_sort_by_key, GapSequence, Gaps, ShellSort};
+pub use spec::{Direction, Nulls, SortSpec};
+pub use tim::{tim_sort, tim_sort_by, tim_sort_by_key, TimSort};
+
+/// Input generators for `sort_bench`, not part of the API.
+#[doc(hidden)]
+pub mod bench {
+    pub use super::inputs::{Distribution, Rng};
+}
+
+/// Common interface of every sorting algorithm in this module.
+///
+/// Only `sort_by` has to be implemented, `sort` and `sort_by_key` mirror the
+/// slice methods of the same name in std.
+pub trait Sorter {
+    /// Whether equal elements keep their relative order.
+    const STABLE: bool;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    fn sort<T>(&self, arr: &mut [T])
+    where
+        T: Ord,
+    {
+        self.sort_by(arr, T::cmp);
+    }
+
+    fn sort_by_key<T, K, F>(&self, arr: &mut [T], mut f: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        self.sort_by(arr, |a, b| f(a).cmp(&f(b)));
+    }
+}
@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+
+// first code point of every block of ten Unicode decimal digits (category
+// Nd) as of Unicode 17.0, the version `char` uses in the current toolchain
+const DIGIT_ZEROS: [u32; 77] = [
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66, 0x0CE6,
+    0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80,
+    0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10, 0x104A0, 0x10D30, 0x10D40, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450,
+    0x114D0, 0x11650, 0x116C0, 0x116D0, 0x116DA, 0x11730, 0x118E0, 0x11950, 0x11BF0, 0x11C50,
+    0x11D50, 0x11DA0, 0x11DE0, 0x11F50, 0x16130, 0x16A60, 0x16AC0, 0x16B50, 0x16D70, 0x1CCF0,
+    0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC, 0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0, 0x1E5F1, 0x1E950,
+    0x1FBF0,
+];
+
+/// Natural ("human") order: runs of digits compare by numeric value, so
+/// `"file9" < "file10"`.
+///
+/// - digits of any script count, `"٣"` and `"３"` are both 3
+/// - numbers have no size limit, they are compared digit by digit
+/// - a digit run sorts before any other character
+/// - other characters compare by code point
+///
+/// Strings that only differ in leading zeros or digit script are still
+/// ordered (`"1" < "01" < "001"`), so the result is a total order that only
+/// returns `Equal` for identical strings.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    compare(a, b, false)
+}
+
+/// `natural_cmp`, but letters are compared by their lowercase form first.
+/// Strings that only differ in case are ordered by code point.
+pub fn natural_cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    compare(a, b, true)
+}
+
+fn compare(mut a: &str, mut b: &str, ignore_case: bool) -> Ordering {
+    // first difference that doesn't count on its own, used if nothing else does
+    let mut tie = Ordering::Equal;
+
+    loop {
+        let (ca, cb) = match (a.chars().next(), b.chars().next()) {
+            (None, None) => return tie,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) => (ca, cb),
+        };
+
+        match (digit(ca).is_some(), digit(cb).is_some()) {
+            (true, true) => {
+                let (na, rest_a) = split_number(a);
+                let (nb, rest_b) = split_number(b);
+                let ord = compare_numbers(na, nb);
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                tie = tie
+                    .then_with(|| na.chars().count().cmp(&nb.chars().count()))
+                    .then_with(|| na.cmp(nb));
+                a = rest_a;
+                b = rest_b;
+            }
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {
+                if ignore_case {
+                    let ord = ca.to_lowercase().cmp(cb.to_lowercase());
+                    if ord != Ordering::Equal {
+                        return ord;
+                    }
+                    tie = tie.then(ca.cmp(&cb));
+                } else if ca != cb {
+                    return ca.cmp(&cb);
+                }
+                a = &a[ca.len_utf8()..];
+                b = &b[cb.len_utf8()..];
+            }
+        }
+    }
+}
+
+fn digit(c: char) -> Option<u32> {
+    let c = c as u32;
+    let i = DIGIT_ZEROS.partition_point(|&zero| zero <= c);
+    let zero = DIGIT_ZEROS[i.checked_sub(1)?];
+    (c - zero < 10).then_some(c - zero)
+}
+
+// splits off the leading run of digits
+fn split_number(s: &str) -> (&str, &str) {
+    let end = s
+        .char_indices()
+        .find(|&(_, c)| digit(c).is_none())
+        .map_or(s.len(), |(i, _)| i);
+    s.split_at(end)
+}
+
+// numeric value of two digit runs, without leading zeros the longer one is larger
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let (a, b) = (significant(a), significant(b));
+    a.clone()
+        .count()
+        .cmp(&b.clone().count())
+        .then_with(|| a.cmp(b))
+}
+
+// digit values without the leading zeros
+fn significant(s: &str) -> impl Iterator<Item = u32> + Clone + '_ {
+    let digits = s.chars().filter_map(digit);
+    let zeros = digits.clone().take_while(|&d| d == 0).count();
+    digits.skip(zeros)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{merge_sort_by, Direction, SortSpec};
+
+    fn sorted(arr: &[&'static str], compare: fn(&str, &str) -> Ordering) -> Vec<&'static str> {
+        let mut arr = arr.to_vec();
+        merge_sort_by(&mut arr, |a, b| compare(a, b));
+        arr
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            sorted(
+                &["file10", "file9", "file1", "file", "file100b", "file100a"],
+                natural_cmp
+            ),
+            vec!["file", "file1", "file9", "file10", "file100a", "file100b"]
+        );
+        assert_eq!(
+            sorted(&["v1.10.0", "v1.2.10", "v1.2.9", "v1.2"], natural_cmp),
+            vec!["v1.2", "v1.2.9", "v1.2.10", "v1.10.0"]
+        );
+    }
+
+    #[test]
+    fn leading_zeros() {
+        assert_eq!(natural_cmp("a01", "a2"), Ordering::Less);
+        assert_eq!(natural_cmp("a010", "a9"), Ordering::Greater);
+        assert_eq!(
+            sorted(&["x001", "x01", "x1", "x0", "x00"], natural_cmp),
+            vec!["x0", "x00", "x1", "x01", "x001"]
+        );
+        // the numbers decide before the leading zeros do
+        assert_eq!(natural_cmp("01b", "1a"), Ordering::Greater);
+        assert_eq!(natural_cmp("01a", "1a"), Ordering::Greater);
+    }
+
+    #[test]
+    fn long_numbers() {
+        assert_eq!(
+            natural_cmp(
+                "id-123456789012345678901234567890",
+                "id-99999999999999999999999999999"
+            ),
+            Ordering::Greater
+        );
+        assert_eq!(
+            natural_cmp("18446744073709551616", "18446744073709551617"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn ignore_case() {
+        assert_eq!(natural_cmp("File10", "file9"), Ordering::Less);
+        assert_eq!(
+            natural_cmp_ignore_case("File10", "file9"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            sorted(&["b2", "a10", "B1", "A9", "a9"], natural_cmp_ignore_case),
+            vec!["A9", "a9", "a10", "B1", "b2"]
+        );
+        assert_eq!(natural_cmp_ignore_case("ÉTÉ2", "été10"), Ordering::Less);
+    }
+
+    #[test]
+    fn mixed_scripts() {
+        // Arabic-Indic, fullwidth and Devanagari digits
+        assert_eq!(natural_cmp("file٣", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("ファイル１０", "ファイル９"), Ordering::Greater);
+        assert_eq!(natural_cmp("पृष्ठ१२", "पृष्ठ३"), Ordering::Greater);
+        assert_eq!(natural_cmp("사진2", "사진١٠"), Ordering::Less);
+
+        // same value, different script: ordered, but after the value
+        assert_ne!(natural_cmp("x٣", "x3"), Ordering::Equal);
+        assert_eq!(natural_cmp("x٣", "x4"), Ordering::Less);
+
+        // not decimal digits
+        assert_eq!(digit('½'), None);
+        assert_eq!(digit('Ⅻ'), None);
+        assert_eq!(digit('/'), None);
+        assert_eq!(digit('𝟘'), Some(0));
+    }
+
+    #[test]
+    fn digit_table() {
+        assert!(DIGIT_ZEROS.windows(2).all(|w| w[0] + 10 <= w[1]));
+        for zero in DIGIT_ZEROS {
+            for d in 0..10 {
+                let c = char::from_u32(zero + d).unwrap();
+                assert!(c.is_numeric(), "{:X}", zero + d);
+                assert_eq!(digit(c), Some(d));
+            }
+        }
+
+        // blocks added in Unicode 15 to 17: Kawi, Garay, Ol Onal
+        assert_eq!(natural_cmp("x\u{11F59}", "x10"), Ordering::Less);
+        assert_eq!(natural_cmp("x\u{10D41}\u{10D40}", "x9"), Ordering::Greater);
+        assert_eq!(digit('\u{1E5F1}'), Some(0));
+        assert_eq!(digit('\u{1E5F0}'), None);
+    }
+
+    #[test]
+    fn total_order() {
+        let arr = ["a1", "a01", "A1", "a١", "a", "", "1", "a1b", "a10"];
+        for compare in [natural_cmp, natural_cmp_ignore_case] {
+            for a in arr {
+                for b in arr {
+                    assert_eq!(compare(a, b), compare(b, a).reverse());
+                    assert_eq!(compare(a, b) == Ordering::Equal, a == b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sort_spec_column() {
+        let mut rows = vec![("kr", "img12"), ("de", "img2"), ("kr", "IMG3")];
+        SortSpec::new()
+            .key(|r: &(&str, &str)| r.0, Direction::Descending)
+            .key_by(|r| r.1, natural_cmp_ignore_case, Direction::Ascending)
+            .sort(&mut rows);
+        assert_eq!(rows, vec![("kr", "IMG3"), ("kr", "img12"), ("de", "img2")]);
+    }
+}
@@ -0,0 +1,186 @@
+use std::cmp::Ordering;
+use std::thread;
+
+use super::merge;
+
+// below this length splitting the work over threads costs more than it saves
+const DEFAULT_CUTOFF: usize = 4096;
+
+/// Parallel merge sort on top of `std::thread::scope`. Stable.
+///
+/// The slice is halved and each half handed to its own thread until either
+/// the thread budget is used up or a half is shorter than the sequential
+/// cutoff, then the halves are sorted with the sequential merge sort and
+/// merged back. Since the sort is stable the result is exactly the same as
+/// the one of `merge_sort_by`.
+pub struct ParallelMergeSort {
+    threads: usize,
+    cutoff: usize,
+}
+
+impl ParallelMergeSort {
+    pub fn new() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            cutoff: DEFAULT_CUTOFF,
+        }
+    }
+
+    /// Maximum number of threads used, including the calling one.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Slices up to this length are sorted on a single thread.
+    pub fn sequential_cutoff(mut self, cutoff: usize) -> Self {
+        self.cutoff = cutoff;
+        self
+    }
+
+    pub fn sort<T>(&self, arr: &mut [T])
+    where
+        T: Ord + Send,
+    {
+        self.sort_by(arr, T::cmp);
+    }
+
+    pub fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        T: Send,
+        F: Fn(&T, &T) -> Ordering + Sync,
+    {
+        let is_less = |a: &T, b: &T| compare(a, b) == Ordering::Less;
+        sort(arr, &is_less, self.threads, self.cutoff);
+    }
+
+    pub fn sort_by_key<T, K, F>(&self, arr: &mut [T], f: F)
+    where
+        T: Send,
+        K: Ord,
+        F: Fn(&T) -> K + Sync,
+    {
+        self.sort_by(arr, |a, b| f(a).cmp(&f(b)));
+    }
+}
+
+impl Default for ParallelMergeSort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn par_merge_sort<T>(arr: &mut [T])
+where
+    T: Ord + Send,
+{
+    ParallelMergeSort::new().sort(arr);
+}
+
+pub fn par_merge_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    ParallelMergeSort::new().sort_by(arr, compare);
+}
+
+pub fn par_merge_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    T: Send,
+    K: Ord,
+    F: Fn(&T) -> K + Sync,
+{
+    ParallelMergeSort::new().sort_by_key(arr, f);
+}
+
+fn sort<T, F>(v: &mut [T], is_less: &F, threads: usize, cutoff: usize)
+where
+    T: Send,
+    F: Fn(&T, &T) -> bool + Sync,
+{
+    let len = v.len();
+    if threads <= 1 || len <= cutoff.max(1) {
+        merge::top_down(v, &mut Vec::new(), &mut |a, b| is_less(a, b));
+        return;
+    }
+
+    let mid = len / 2;
+    let (left, right) = v.split_at_mut(mid);
+    thread::scope(|s| {
+        s.spawn(|| sort(left, is_less, threads / 2, cutoff));
+        sort(right, is_less, threads - threads / 2, cutoff);
+    });
+
+    merge::merge(v, mid, &mut Vec::new(), &mut |a, b| is_less(a, b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{merge_sort_by_key, Distribution};
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        ParallelMergeSort::new()
+            .threads(4)
+            .sequential_cutoff(2)
+            .sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let mut arr: Vec<i32> = vec![];
+        par_merge_sort(&mut arr);
+        assert!(arr.is_empty());
+
+        let mut arr = vec![1];
+        ParallelMergeSort::new()
+            .threads(8)
+            .sequential_cutoff(0)
+            .sort(&mut arr);
+        assert_eq!(arr, vec![1]);
+    }
+
+    #[test]
+    fn same_as_sequential() {
+        let records: Vec<(u64, usize)> = Distribution::Random
+            .generate(20_000, 11)
+            .iter()
+            .map(|k| k % 100)
+            .zip(0..)
+            .collect();
+        let mut expected = records.clone();
+        merge_sort_by_key(&mut expected, |&(k, _)| k);
+
+        for threads in [1, 2, 3, 4, 7, 16] {
+            for cutoff in [0, 100, 1000, 100_000] {
+                let mut arr = records.clone();
+                ParallelMergeSort::new()
+                    .threads(threads)
+                    .sequential_cutoff(cutoff)
+                    .sort_by_key(&mut arr, |&(k, _)| k);
+                assert_eq!(arr, expected, "threads {} cutoff {}", threads, cutoff);
+            }
+        }
+    }
+
+    #[test]
+    fn descending_strings() {
+        let mut arr: Vec<String> = Distribution::Random
+            .generate(10_000, 2)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+
+        par_merge_sort_by(&mut arr, |a, b| b.cmp(a));
+        assert_eq!(arr, expected);
+
+        par_merge_sort_by_key(&mut arr, |s| s.len());
+        assert!(arr.windows(2).all(|w| w[0].len() <= w[1].len()));
+    }
+}
@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+
+/// How far a slice is from sorted, see `presortedness`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Presortedness {
+    pub len: usize,
+    /// Pairs `i < j` with `arr[j] < arr[i]`. 0 when sorted, `n(n-1)/2` when
+    /// strictly descending.
+    pub inversions: u64,
+    /// Maximal non-descending runs, 1 when sorted (0 when empty).
+    pub runs: usize,
+    /// Length of the longest non-decreasing subsequence, `len` when sorted.
+    pub lis: usize,
+}
+
+impl Presortedness {
+    pub fn is_sorted(&self) -> bool {
+        self.inversions == 0
+    }
+
+    /// Inversions relative to the maximum, from 0.0 (sorted) to 1.0 (strictly
+    /// descending).
+    pub fn inversion_ratio(&self) -> f64 {
+        let max = self.len as u64 * self.len.saturating_sub(1) as u64 / 2;
+        if max == 0 {
+            return 0.0;
+        }
+        self.inversions as f64 / max as f64
+    }
+
+    /// Elements that have to be removed to leave a sorted sequence.
+    pub fn rem(&self) -> usize {
+        self.len - self.lis
+    }
+}
+
+/// All presortedness measures of `arr` at once, O(n log n).
+pub fn presortedness<T: Ord>(arr: &[T]) -> Presortedness {
+    presortedness_by(arr, T::cmp)
+}
+
+pub fn presortedness_by<T, F>(arr: &[T], mut compare: F) -> Presortedness
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    Presortedness {
+        len: arr.len(),
+        inversions: inversions_by(arr, &mut compare),
+        runs: runs_by(arr, &mut compare),
+        lis: lis_len_by(arr, &mut compare),
+    }
+}
+
+pub fn is_sorted<T: Ord>(arr: &[T]) -> bool {
+    is_sorted_by(arr, T::cmp)
+}
+
+/// Whether no element is less than the one before it, O(n).
+pub fn is_sorted_by<T, F>(arr: &[T], mut compare: F) -> bool
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    arr.windows(2)
+        .all(|w| compare(&w[1], &w[0]) != Ordering::Less)
+}
+
+pub fn inversions<T: Ord>(arr: &[T]) -> u64 {
+    inversions_by(arr, T::cmp)
+}
+
+/// Number of pairs `i < j` with `arr[j] < arr[i]`, O(n log n).
+///
+/// Merge sorts the indices of `arr`: whenever an element of the right half
+/// is taken before the rest of the left half, it is inverted with all of
+/// them. Equal elements don't count.
+pub fn inversions_by<T, F>(arr: &[T], mut compare: F) -> u64
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut idx: Vec<usize> = (0..arr.len()).collect();
+    let mut buf = Vec::with_capacity(arr.len());
+    let mut is_less = |&a: &usize, &b: &usize| compare(&arr[a], &arr[b]) == Ordering::Less;
+
+    let mut inversions = 0;
+    let mut width = 1;
+    while width < idx.len() {
+        for chunk in idx.chunks_mut(2 * width) {
+            if chunk.len() > width {
+                inversions += merge_counting(chunk, width, &mut buf, &mut is_less);
+            }
+        }
+        width *= 2;
+    }
+    inversions
+}
+
+// merges the sorted `v[..mid]` and `v[mid..]`, returns the inversions between them
+fn merge_counting<F>(v: &mut [usize], mid: usize, buf: &mut Vec<usize>, is_less: &mut F) -> u64
+where
+    F: FnMut(&usize, &usize) -> bool,
+{
+    buf.clear();
+    buf.extend_from_slice(&v[..mid]);
+
+    let (mut l, mut r, mut out) = (0, mid, 0);
+    let mut inversions = 0;
+    while l < buf.len() && r < v.len() {
+        if is_less(&v[r], &buf[l]) {
+            inversions += (buf.len() - l) as u64;
+            v[out] = v[r];
+            r += 1;
+        } else {
+            v[out] = buf[l];
+            l += 1;
+        }
+        out += 1;
+    }
+    // whatever is left of the right half is already in place
+    v[out..out + buf.len() - l].copy_from_slice(&buf[l..]);
+    inversions
+}
+
+pub fn runs<T: Ord>(arr: &[T]) -> usize {
+    runs_by(arr, T::cmp)
+}
+
+/// Number of maximal non-descending runs, O(n).
+pub fn runs_by<T, F>(arr: &[T], mut compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if arr.is_empty() {
+        return 0;
+    }
+    1 + arr
+        .windows(2)
+        .filter(|w| compare(&w[1], &w[0]) == Ordering::Less)
+        .count()
+}
+
+pub fn lis_len<T: Ord>(arr: &[T]) -> usize {
+    lis_len_by(arr, T::cmp)
+}
+
+/// Length of the longest non-decreasing subsequence, O(n log n).
+///
+/// Patience sorting: `tails[k]` is the smallest element that ends a
+/// subsequence of length `k + 1` so far.
+pub fn lis_len_by<T, F>(arr: &[T], mut compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut tails: Vec<&T> = Vec::new();
+    for x in arr {
+        let k = tails.partition_point(|t| compare(x, t) != Ordering::Less);
+        if k == tails.len() {
+            tails.push(x);
+        } else {
+            tails[k] = x;
+        }
+    }
+    tails.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{Distribution, Rng};
+
+    fn naive_inversions(arr: &[u64]) -> u64 {
+        let mut count = 0;
+        for i in 0..arr.len() {
+            for j in i + 1..arr.len() {
+                if arr[j] < arr[i] {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    // O(n^2) dynamic programming
+    fn naive_lis(arr: &[u64]) -> usize {
+        let mut best = vec![1; arr.len()];
+        for j in 0..arr.len() {
+            for i in 0..j {
+                if arr[i] <= arr[j] {
+                    best[j] = best[j].max(best[i] + 1);
+                }
+            }
+        }
+        best.into_iter().max().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_presortedness() {
+        let report = presortedness(&[3, 1, 2, 5, 4]);
+        assert_eq!(
+            report,
+            Presortedness {
+                len: 5,
+                inversions: 3,
+                runs: 3,
+                lis: 3,
+            }
+        );
+        assert!(!report.is_sorted());
+        assert_eq!(report.rem(), 2);
+        assert_eq!(report.inversion_ratio(), 0.3);
+    }
+
+    #[test]
+    fn sorted_and_reversed() {
+        let sorted: Vec<u32> = (0..100).collect();
+        let report = presortedness(&sorted);
+        assert!(report.is_sorted() && is_sorted(&sorted));
+        assert_eq!(
+            (report.runs, report.lis, report.inversion_ratio()),
+            (1, 100, 0.0)
+        );
+
+        let reversed: Vec<u32> = (0..100).rev().collect();
+        let report = presortedness(&reversed);
+        assert_eq!(report.inversions, 100 * 99 / 2);
+        assert_eq!(
+            (report.runs, report.lis, report.inversion_ratio()),
+            (100, 1, 1.0)
+        );
+
+        let empty: [u32; 0] = [];
+        assert_eq!(presortedness(&empty).runs, 0);
+        assert_eq!(presortedness(&empty).inversion_ratio(), 0.0);
+        assert!(is_sorted(&empty) && is_sorted(&[1]));
+    }
+
+    #[test]
+    fn equal_elements() {
+        let arr = [2, 2, 1, 1, 2, 2];
+        assert_eq!(inversions(&arr), 4);
+        assert_eq!(runs(&arr), 2);
+        assert_eq!(lis_len(&arr), 4);
+        assert!(is_sorted(&[1, 1, 1]));
+    }
+
+    #[test]
+    fn matches_naive() {
+        let mut rng = Rng::new(3);
+        for distribution in Distribution::ALL {
+            for len in [0, 1, 2, 7, 64, 100, 333] {
+                let arr = distribution.generate(len, rng.next_u64());
+                assert_eq!(
+                    inversions(&arr),
+                    naive_inversions(&arr),
+                    "{}",
+                    distribution.name()
+                );
+                assert_eq!(lis_len(&arr), naive_lis(&arr), "{}", distribution.name());
+                assert_eq!(is_sorted(&arr), naive_inversions(&arr) == 0);
+            }
+        }
+    }
+
+    #[test]
+    fn custom_comparator() {
+        let arr = [5, 4, 4, 1];
+        let desc = |a: &i32, b: &i32| b.cmp(a);
+        assert!(is_sorted_by(&arr, desc));
+        assert_eq!(inversions_by(&arr, desc), 0);
+        assert_eq!(runs_by(&arr, desc), 1);
+        assert_eq!(lis_len_by(&arr, desc), 4);
+        assert_eq!(presortedness_by(&arr, desc).rem(), 0);
+    }
+
+    #[test]
+    fn large_input() {
+        let n = 100_000u64;
+        let arr: Vec<u64> = (0..n).rev().collect();
+        assert_eq!(inversions(&arr), n * (n - 1) / 2);
+    }
+}
@@ -0,0 +1,334 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::{heap, insertion, Sorter};
+
+// slices up to this length are finished with insertion sort
+pub(crate) const INSERTION_THRESHOLD: usize = 16;
+// from this length on the pivot is the ninther instead of the median of three
+const NINTHER_THRESHOLD: usize = 128;
+
+/// Introsort-style quicksort. Not stable.
+///
+/// Pivots are picked with median of three (or Tukey's ninther on long
+/// slices) and small partitions go through insertion sort. When the
+/// recursion gets deeper than `2 * log2(n)` the remaining slice is heap
+/// sorted, so the worst case stays O(n log n).
+pub struct QuickSort;
+
+impl Sorter for QuickSort {
+    const STABLE: bool = false;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_observed(arr, compare, &mut ());
+    }
+}
+
+impl ObservedSorter for QuickSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        let limit = 2 * arr.len().max(1).ilog2();
+        introsort(
+            arr,
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            limit,
+            &mut Trace::new(observer),
+        );
+    }
+}
+
+pub fn quick_sort<T: Ord>(arr: &mut [T]) {
+    QuickSort.sort(arr);
+}
+
+pub fn quick_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    QuickSort.sort_by(arr, compare);
+}
+
+pub fn quick_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    QuickSort.sort_by_key(arr, f);
+}
+
+pub(crate) fn introsort<T, F, O>(
+    mut v: &mut [T],
+    is_less: &mut F,
+    mut limit: u32,
+    trace: &mut Trace<O>,
+) where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    // offset of `v` inside the slice `trace` reports on
+    let mut base = 0;
+    loop {
+        let trace = &mut trace.at(base);
+        let len = v.len();
+        if len <= INSERTION_THRESHOLD {
+            insertion::sort_observed(v, is_less, trace);
+            return;
+        }
+
+        if limit == 0 {
+            heap::sort_observed(v, is_less, trace);
+            return;
+        }
+        limit -= 1;
+
+        let pivot = choose_pivot(v, is_less, trace);
+        v.swap(0, pivot);
+        trace.swap(0, pivot);
+        let mid = partition(v, is_less, trace);
+
+        // recurse into the smaller side and loop on the larger one,
+        // so the stack stays O(log n)
+        let (left, right) = v.split_at_mut(mid);
+        let right = &mut right[1..];
+        if left.len() < right.len() {
+            introsort(left, is_less, limit, trace);
+            v = right;
+            base += mid + 1;
+        } else {
+            introsort(right, is_less, limit, &mut trace.at(mid + 1));
+            v = left;
+        }
+    }
+}
+
+pub(crate) fn choose_pivot<T, F, O>(v: &[T], is_less: &mut F, trace: &mut Trace<O>) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    let (a, b, c) = (0, len / 2, len - 1);
+
+    if len < NINTHER_THRESHOLD {
+        return median3(v, a, b, c, is_less, trace);
+    }
+
+    let step = len / 8;
+    let a = median3(v, a, a + step, a + 2 * step, is_less, trace);
+    let b = median3(v, b - step, b, b + step, is_less, trace);
+    let c = median3(v, c - 2 * step, c - step, c, is_less, trace);
+    median3(v, a, b, c, is_less, trace)
+}
+
+fn median3<T, F, O>(
+    v: &[T],
+    mut a: usize,
+    mut b: usize,
+    mut c: usize,
+    is_less: &mut F,
+    trace: &mut Trace<O>,
+) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    trace.compare(b, a);
+    if is_less(&v[b], &v[a]) {
+        mem::swap(&mut a, &mut b);
+    }
+    trace.compare(c, b);
+    if is_less(&v[c], &v[b]) {
+        mem::swap(&mut b, &mut c);
+    }
+    trace.compare(b, a);
+    if is_less(&v[b], &v[a]) {
+        mem::swap(&mut a, &mut b);
+    }
+    b
+}
+
+/// Partitions `v` around the pivot at `v[0]` and returns its final index.
+///
+/// Both scans stop on elements equal to the pivot, so runs of equal keys are
+/// split evenly instead of piling up on one side.
+pub(crate) fn partition<T, F, O>(v: &mut [T], is_less: &mut F, trace: &mut Trace<O>) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let (head, rest) = v.split_at_mut(1);
+    let pivot = &head[0];
+
+    // `rest[i]` is `v[i + 1]`
+    let mut l = 0;
+    let mut r = rest.len();
+    loop {
+        while l < r {
+            trace.compare(l + 1, 0);
+            if !is_less(&rest[l], pivot) {
+                break;
+            }
+            l += 1;
+        }
+        while l < r {
+            trace.compare(0, r);
+            if !is_less(pivot, &rest[r - 1]) {
+                break;
+            }
+            r -= 1;
+        }
+        if l >= r {
+            break;
+        }
+
+        r -= 1;
+        rest.swap(l, r);
+        trace.swap(l + 1, r + 1);
+        l += 1;
+    }
+
+    v.swap(0, l);
+    trace.swap(0, l);
+    l
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Distribution;
+
+    fn n_log_n(n: usize) -> usize {
+        n * n.ilog2() as usize
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        quick_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let mut arr: Vec<i32> = vec![];
+        quick_sort(&mut arr);
+        assert!(arr.is_empty());
+
+        let mut arr = vec![1];
+        quick_sort(&mut arr);
+        assert_eq!(arr, vec![1]);
+    }
+
+    #[test]
+    fn random_input() {
+        for len in [17, 100, 127, 128, 1000, 10_000] {
+            let mut arr = Distribution::Random.generate(len, len as u64);
+            let mut expected = arr.clone();
+            expected.sort();
+
+            quick_sort(&mut arr);
+            assert_eq!(arr, expected);
+        }
+    }
+
+    #[test]
+    fn few_unique() {
+        let mut arr: Vec<u64> = Distribution::Random
+            .generate(10_000, 1)
+            .iter()
+            .map(|n| n % 3)
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let mut comparisons = 0;
+        quick_sort_by(&mut arr, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+        assert_eq!(arr, expected);
+        assert!(comparisons < 4 * n_log_n(arr.len()));
+    }
+
+    #[test]
+    fn by_key_descending() {
+        let mut arr = vec![(1, "a"), (3, "c"), (2, "b"), (4, "d")];
+        quick_sort_by_key(&mut arr, |&(n, _)| std::cmp::Reverse(n));
+        assert_eq!(arr, vec![(4, "d"), (3, "c"), (2, "b"), (1, "a")]);
+    }
+
+    // Musser's median-of-3 killer sequence, quadratic for a quicksort that
+    // always takes the median of first, middle and last.
+    #[test]
+    fn median_of_three_killer() {
+        let n = 4096;
+        let k = n / 2;
+        let mut arr = vec![0; n];
+        for i in 1..=k {
+            if i % 2 == 1 {
+                arr[i - 1] = i;
+                arr[i] = k + i;
+            }
+            arr[k + i - 1] = 2 * i;
+        }
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let mut comparisons = 0;
+        quick_sort_by(&mut arr, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+        assert_eq!(arr, expected);
+        assert!(comparisons < 4 * n_log_n(n));
+    }
+
+    // McIlroy's "A Killer Adversary for Quicksort": values are only fixed
+    // when the comparator has to, which drives any plain quicksort quadratic.
+    #[test]
+    fn adversarial_comparator() {
+        let n = 4096;
+        let gas = n;
+        let mut val = vec![gas; n];
+        let mut solid = 0;
+        let mut candidate = 0;
+        let mut comparisons = 0;
+
+        let mut arr: Vec<usize> = (0..n).collect();
+        quick_sort_by(&mut arr, |&x, &y| {
+            comparisons += 1;
+            if val[x] == gas && val[y] == gas {
+                if x == candidate {
+                    val[x] = solid;
+                } else {
+                    val[y] = solid;
+                }
+                solid += 1;
+            }
+
+            if val[x] == gas {
+                candidate = x;
+            } else if val[y] == gas {
+                candidate = y;
+            }
+            val[x].cmp(&val[y])
+        });
+
+        assert!(arr.windows(2).all(|w| val[w[0]] <= val[w[1]]));
+        assert!(comparisons < 8 * n_log_n(n));
+    }
+
+    #[test]
+    fn partition_equal_keys() {
+        let mut arr = vec![5; 33];
+        let mid = partition(&mut arr, &mut |a, b| a < b, &mut Trace::new(&mut ()));
+        assert!(mid > 8 && mid < 24);
+    }
+}
@@ -0,0 +1,332 @@
+use std::mem;
+
+use super::insertion;
+
+// buckets up to this length are finished with insertion sort in the MSD sort
+const MSD_THRESHOLD: usize = 32;
+
+/// Integer key usable by the LSD radix sort.
+///
+/// `byte(n)` returns the `n`-th least significant byte of the key, with the
+/// sign bit flipped for signed types so that negative values order first.
+pub trait RadixKey: Copy {
+    const BYTES: usize;
+
+    fn byte(self, n: usize) -> u8;
+}
+
+macro_rules! radix_key_unsigned {
+    ($($t:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = mem::size_of::<$t>();
+
+            fn byte(self, n: usize) -> u8 {
+                (self >> (n * 8)) as u8
+            }
+        }
+    )*};
+}
+
+macro_rules! radix_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            const BYTES: usize = mem::size_of::<$t>();
+
+            fn byte(self, n: usize) -> u8 {
+                let flipped = (self as $u) ^ (1 << (<$u>::BITS - 1));
+                (flipped >> (n * 8)) as u8
+            }
+        }
+    )*};
+}
+
+radix_key_unsigned!(u8, u16, u32, u64, u128, usize);
+radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+/// LSD radix sort for primitive integers. Stable, O(n * size_of::<K>()).
+pub fn radix_sort<K: RadixKey>(arr: &mut [K]) {
+    lsd(arr, |&k| k);
+}
+
+/// LSD radix sort of records by an integer key. Stable.
+///
+/// The key is extracted once per element, the records themselves are only
+/// moved once at the end.
+pub fn radix_sort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: RadixKey,
+    F: FnMut(&T) -> K,
+{
+    let mut keyed: Vec<(K, usize)> = arr.iter().map(&mut f).zip(0..).collect();
+    lsd(&mut keyed, |&(k, _)| k);
+
+    let mut perm: Vec<usize> = keyed.into_iter().map(|(_, idx)| idx).collect();
+    apply_permutation(arr, &mut perm);
+}
+
+/// MSD radix sort (American flag sort) for byte strings. Not stable.
+pub fn msd_radix_sort<T: AsRef<[u8]>>(arr: &mut [T]) {
+    msd(arr, |x: &T| x.as_ref());
+}
+
+/// MSD radix sort of records by a byte string key. Not stable.
+pub fn msd_radix_sort_by_key<T, F>(arr: &mut [T], f: F)
+where
+    F: Fn(&T) -> &[u8],
+{
+    msd(arr, f);
+}
+
+fn lsd<T, K, F>(v: &mut [T], key: F)
+where
+    T: Copy,
+    K: RadixKey,
+    F: Fn(&T) -> K,
+{
+    if v.len() <= 1 {
+        return;
+    }
+
+    let mut buf = v.to_vec();
+    for n in 0..K::BYTES {
+        let mut counts = [0; 256];
+        for x in v.iter() {
+            counts[key(x).byte(n) as usize] += 1;
+        }
+
+        // every key has the same byte here, the pass wouldn't move anything
+        if counts.contains(&v.len()) {
+            continue;
+        }
+
+        let mut offsets = [0; 256];
+        for b in 1..256 {
+            offsets[b] = offsets[b - 1] + counts[b - 1];
+        }
+
+        for x in v.iter() {
+            let b = key(x).byte(n) as usize;
+            buf[offsets[b]] = *x;
+            offsets[b] += 1;
+        }
+        v.copy_from_slice(&buf);
+    }
+}
+
+fn msd<T, F>(arr: &mut [T], key: F)
+where
+    F: Fn(&T) -> &[u8],
+{
+    // (start, end, depth) of the buckets left to sort; an explicit stack
+    // because long shared prefixes would go one frame deeper per byte
+    let mut work = vec![(0, arr.len(), 0)];
+    while let Some((start, end, depth)) = work.pop() {
+        let v = &mut arr[start..end];
+        if v.len() <= MSD_THRESHOLD {
+            insertion::sort(v, &mut |a, b| key(a)[depth..] < key(b)[depth..]);
+            continue;
+        }
+
+        // bucket 0 holds the keys that end at `depth`
+        let bucket = |x: &T| key(x).get(depth).map_or(0, |&b| b as usize + 1);
+
+        let mut counts = [0; 257];
+        for x in v.iter() {
+            counts[bucket(x)] += 1;
+        }
+
+        let mut starts = [0; 257];
+        for b in 1..257 {
+            starts[b] = starts[b - 1] + counts[b - 1];
+        }
+
+        // permute in place: swap each element into the next free slot of its bucket
+        let mut next = starts;
+        for b in 0..257 {
+            let end = starts[b] + counts[b];
+            while next[b] < end {
+                let d = bucket(&v[next[b]]);
+                if d == b {
+                    next[b] += 1;
+                } else {
+                    v.swap(next[b], next[d]);
+                    next[d] += 1;
+                }
+            }
+        }
+
+        for b in 1..257 {
+            if counts[b] > 1 {
+                let bucket_start = start + starts[b];
+                work.push((bucket_start, bucket_start + counts[b], depth + 1));
+            }
+        }
+    }
+}
+
+/// Reorders `v` so that `v[i]` becomes the old `v[perm[i]]`, by following
+/// the cycles of the permutation. `perm` is left as the identity.
+pub(crate) fn apply_permutation<T>(v: &mut [T], perm: &mut [usize]) {
+    for i in 0..v.len() {
+        let mut j = i;
+        loop {
+            let k = perm[j];
+            perm[j] = j;
+            if k == i {
+                break;
+            }
+            v.swap(j, k);
+            j = k;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Distribution;
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        radix_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn signed_extremes() {
+        let mut arr = vec![0, -1, i32::MAX, 1, i32::MIN, -100, i32::MIN + 1, 100];
+        let mut expected = arr.clone();
+        expected.sort();
+        radix_sort(&mut arr);
+        assert_eq!(arr, expected);
+
+        let mut arr = vec![i8::MAX, 0, -1, i8::MIN, 1];
+        radix_sort(&mut arr);
+        assert_eq!(arr, vec![i8::MIN, -1, 0, 1, i8::MAX]);
+
+        let mut arr = vec![i128::MAX, 0, -1, i128::MIN, 1];
+        radix_sort(&mut arr);
+        assert_eq!(arr, vec![i128::MIN, -1, 0, 1, i128::MAX]);
+    }
+
+    #[test]
+    fn all_widths() {
+        let values = Distribution::Random.generate(1000, 42);
+
+        macro_rules! check {
+            ($($t:ty),*) => {$(
+                let mut arr: Vec<$t> = values.iter().map(|&n| n as $t).collect();
+                let mut expected = arr.clone();
+                expected.sort();
+                radix_sort(&mut arr);
+                assert_eq!(arr, expected, stringify!($t));
+            )*};
+        }
+
+        check!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+    }
+
+    #[test]
+    fn records_by_key_stable() {
+        let records: Vec<(i16, usize)> = Distribution::Random
+            .generate(5000, 3)
+            .iter()
+            .map(|&n| (n as i16) % 50)
+            .zip(0..)
+            .collect();
+        let mut expected = records.clone();
+        expected.sort_by_key(|&(k, _)| k);
+
+        let mut arr: Vec<(String, (i16, usize))> =
+            records.iter().map(|&r| (format!("{:?}", r), r)).collect();
+        radix_sort_by_key(&mut arr, |(_, (k, _))| *k);
+
+        let sorted: Vec<(i16, usize)> = arr.into_iter().map(|(_, r)| r).collect();
+        assert_eq!(sorted, expected);
+    }
+
+    #[test]
+    fn byte_strings() {
+        let mut arr = vec!["banana", "apple", "", "app", "b", "apples", "a", "banana"];
+        let mut expected = arr.clone();
+        expected.sort();
+        msd_radix_sort(&mut arr);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn strings_with_large_buckets() {
+        let mut arr: Vec<String> = Distribution::Random
+            .generate(3000, 9)
+            .iter()
+            .map(|&n| format!("{:x}", n >> (n % 60)))
+            .collect();
+        arr.push(String::new());
+        arr.push("\u{ff}\u{0}".to_string());
+        let mut expected = arr.clone();
+        expected.sort();
+        msd_radix_sort(&mut arr);
+        assert_eq!(arr, expected);
+
+        let mut bytes: Vec<Vec<u8>> = Distribution::Random
+            .generate(2000, 10)
+            .iter()
+            .map(|&n| n.to_le_bytes()[..(n % 9) as usize].to_vec())
+            .collect();
+        let mut expected = bytes.clone();
+        expected.sort();
+        msd_radix_sort(&mut bytes);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn long_shared_prefix() {
+        // one bucket per byte of the prefix, deep enough to overflow a recursive sort
+        let prefix = "x".repeat(20_000);
+        let mut arr: Vec<String> = Distribution::Random
+            .generate(64, 11)
+            .iter()
+            .map(|&n| format!("{}{}", prefix, n % 1000))
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort();
+        msd_radix_sort(&mut arr);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn records_by_string_key() {
+        struct User {
+            name: String,
+            id: u32,
+        }
+
+        let mut users: Vec<User> = Distribution::Random
+            .generate(500, 5)
+            .iter()
+            .map(|&n| User {
+                name: format!("user{}", n % 1000),
+                id: n as u32,
+            })
+            .collect();
+        let mut expected: Vec<String> = users.iter().map(|u| u.name.clone()).collect();
+        expected.sort();
+
+        msd_radix_sort_by_key(&mut users, |u| u.name.as_bytes());
+        let names: Vec<String> = users.iter().map(|u| u.name.clone()).collect();
+        assert_eq!(names, expected);
+
+        radix_sort_by_key(&mut users, |u| u.id);
+        assert!(users.windows(2).all(|w| w[0].id <= w[1].id));
+    }
+
+    #[test]
+    fn permutation() {
+        let mut arr = vec!['a', 'b', 'c', 'd', 'e'];
+        let mut perm = vec![3, 0, 4, 1, 2];
+        apply_permutation(&mut arr, &mut perm);
+        assert_eq!(arr, vec!['d', 'a', 'e', 'b', 'c']);
+        assert_eq!(perm, vec![0, 1, 2, 3, 4]);
+    }
+}
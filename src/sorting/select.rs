@@ -0,0 +1,434 @@
+use std::cmp::Ordering;
+use std::vec;
+
+use super::instrument::Trace;
+use super::quick::{self, INSERTION_THRESHOLD};
+use super::{heap, insertion};
+
+/// Reorders `arr` so that `arr[n]` is the element that would be there after
+/// sorting, everything before it is not greater and everything after it is
+/// not smaller. Returns that element.
+///
+/// Quickselect with the pivots and partitioning of `QuickSort`. Once it
+/// takes more than `2 * log2(n)` rounds the rest is heap sorted, so it is
+/// O(n) on average and O(n log n) in the worst case. Not stable.
+///
+/// Panics if `n >= arr.len()`.
+pub fn select_nth<T: Ord>(arr: &mut [T], n: usize) -> &mut T {
+    select_nth_by(arr, n, T::cmp)
+}
+
+pub fn select_nth_by<T, F>(arr: &mut [T], n: usize, mut compare: F) -> &mut T
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    assert!(
+        n < arr.len(),
+        "index {} out of range for slice of length {}",
+        n,
+        arr.len()
+    );
+    select(arr, n, &mut |a, b| compare(a, b) == Ordering::Less);
+    &mut arr[n]
+}
+
+pub fn select_nth_by_key<T, K, F>(arr: &mut [T], n: usize, mut f: F) -> &mut T
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    select_nth_by(arr, n, |a, b| f(a).cmp(&f(b)))
+}
+
+/// Sorts the `k` smallest elements into `arr[..k]`, the order of the rest is
+/// unspecified. O(n + k log k) on average. Not stable.
+pub fn partial_sort<T: Ord>(arr: &mut [T], k: usize) {
+    partial_sort_by(arr, k, T::cmp);
+}
+
+pub fn partial_sort_by<T, F>(arr: &mut [T], k: usize, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let is_less = &mut |a: &T, b: &T| compare(a, b) == Ordering::Less;
+    let k = k.min(arr.len());
+    if k == 0 {
+        return;
+    }
+
+    if k < arr.len() {
+        select(arr, k - 1, is_less);
+    }
+    let limit = 2 * k.ilog2();
+    quick::introsort(&mut arr[..k], is_less, limit, &mut Trace::new(&mut ()));
+}
+
+pub fn partial_sort_by_key<T, K, F>(arr: &mut [T], k: usize, mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    partial_sort_by(arr, k, |a, b| f(a).cmp(&f(b)));
+}
+
+fn select<T, F>(mut v: &mut [T], mut n: usize, is_less: &mut F)
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    let mut observer = ();
+    let trace = &mut Trace::new(&mut observer);
+    let mut limit = 2 * v.len().max(1).ilog2();
+    loop {
+        if v.len() <= INSERTION_THRESHOLD {
+            insertion::sort(v, is_less);
+            return;
+        }
+
+        if limit == 0 {
+            heap::sort(v, is_less);
+            return;
+        }
+        limit -= 1;
+
+        let pivot = quick::choose_pivot(v, is_less, trace);
+        v.swap(0, pivot);
+        let mid = quick::partition(v, is_less, trace);
+
+        let (left, right) = v.split_at_mut(mid);
+        match n.cmp(&mid) {
+            Ordering::Equal => return,
+            Ordering::Less => v = left,
+            Ordering::Greater => {
+                v = &mut right[1..];
+                n -= mid + 1;
+            }
+        }
+    }
+}
+
+/// Keeps the first `k` elements, in sorted order, of everything pushed into
+/// it, in O(k) memory and O(log k) per push.
+///
+/// The kept elements sit in a max heap (same layout as
+/// `data_structures::heap::Heap`), so the largest of them is on top and is
+/// replaced whenever something smaller comes along. Of equal elements the
+/// ones pushed first are kept. For the `k` largest pass a reversed
+/// comparator.
+pub struct TopK<T, F> {
+    k: usize,
+    items: Vec<T>,
+    compare: F,
+}
+
+// comparator of `TopK::new` and `TopKExt::top_k`, plain `Ord::cmp`
+pub(crate) type CompareFn<T> = fn(&T, &T) -> Ordering;
+
+impl<T: Ord> TopK<T, CompareFn<T>> {
+    pub fn new(k: usize) -> Self {
+        Self::new_by(k, T::cmp)
+    }
+}
+
+impl<T, F> TopK<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    pub fn new_by(k: usize, compare: F) -> Self {
+        Self {
+            k,
+            // grows with the input, `k` may be far larger than the stream
+            items: Vec::new(),
+            compare,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        let compare = &mut self.compare;
+        let is_less = &mut |a: &T, b: &T| compare(a, b) == Ordering::Less;
+
+        if self.items.len() < self.k {
+            self.items.push(item);
+            let last = self.items.len() - 1;
+            heap::sift_up(&mut self.items, last, is_less);
+        } else if self.k > 0 && is_less(&item, &self.items[0]) {
+            self.items[0] = item;
+            heap::sift_down(&mut self.items, 0, is_less);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let compare = &mut self.compare;
+        heap::sort(&mut self.items, &mut |a, b| compare(a, b) == Ordering::Less);
+        self.items
+    }
+}
+
+impl<T, F> Extend<T> for TopK<T, F>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+/// Iterator returned by `TopKExt::top_k`. Nothing is read from the source
+/// until the first call to `next`.
+pub struct TopKIter<I: Iterator, F> {
+    state: TopKState<I, F>,
+}
+
+enum TopKState<I: Iterator, F> {
+    Pending(I, TopK<I::Item, F>),
+    Sorted(vec::IntoIter<I::Item>),
+    Empty,
+}
+
+impl<I, F> Iterator for TopKIter<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if let TopKState::Pending(..) = self.state {
+            if let TopKState::Pending(source, mut top) =
+                std::mem::replace(&mut self.state, TopKState::Empty)
+            {
+                top.extend(source);
+                self.state = TopKState::Sorted(top.into_sorted_vec().into_iter());
+            }
+        }
+
+        match &mut self.state {
+            TopKState::Sorted(iter) => iter.next(),
+            _ => None,
+        }
+    }
+}
+
+/// Streaming top-k on any iterator: yields the first `k` elements of the
+/// sorted order (the `k` smallest) in O(n log k) time and O(k) memory.
+pub trait TopKExt: Iterator + Sized {
+    fn top_k(self, k: usize) -> TopKIter<Self, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+    {
+        TopKIter {
+            state: TopKState::Pending(self, TopK::new(k)),
+        }
+    }
+
+    fn top_k_by<F>(self, k: usize, compare: F) -> TopKIter<Self, F>
+    where
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        TopKIter {
+            state: TopKState::Pending(self, TopK::new_by(k, compare)),
+        }
+    }
+
+    fn top_k_by_key<K, F>(
+        self,
+        k: usize,
+        mut f: F,
+    ) -> TopKIter<Self, impl FnMut(&Self::Item, &Self::Item) -> Ordering>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K,
+    {
+        self.top_k_by(k, move |a, b| f(a).cmp(&f(b)))
+    }
+}
+
+impl<I: Iterator> TopKExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{Distribution, Rng};
+
+    #[test]
+    fn test_select() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        assert_eq!(*select_nth(&mut arr, 0), 1);
+        assert_eq!(*select_nth(&mut arr, 3), 4);
+        assert_eq!(*select_nth(&mut arr, 7), 8);
+    }
+
+    #[test]
+    fn select_every_position() {
+        let input = Distribution::Random.generate(200, 1);
+        let mut sorted = input.clone();
+        sorted.sort();
+
+        for n in 0..input.len() {
+            let mut arr = input.clone();
+            let nth = *select_nth(&mut arr, n);
+            assert_eq!(nth, sorted[n]);
+            assert!(arr[..n].iter().all(|&x| x <= nth));
+            assert!(arr[n + 1..].iter().all(|&x| x >= nth));
+        }
+    }
+
+    #[test]
+    fn select_large_and_few_unique() {
+        for modulo in [2, 10, u64::MAX] {
+            let input: Vec<u64> = Distribution::Random
+                .generate(50_000, modulo)
+                .iter()
+                .map(|n| n % modulo)
+                .collect();
+            let mut sorted = input.clone();
+            sorted.sort();
+
+            for n in [0, 1, 25_000, 49_999] {
+                let mut arr = input.clone();
+                assert_eq!(*select_nth(&mut arr, n), sorted[n]);
+            }
+        }
+    }
+
+    #[test]
+    fn select_by_key_largest() {
+        let mut arr = vec![("a", 3), ("b", 9), ("c", 1), ("d", 7)];
+        let second_largest = select_nth_by(&mut arr, 1, |a, b| b.1.cmp(&a.1));
+        assert_eq!(*second_largest, ("d", 7));
+        assert_eq!(*select_nth_by_key(&mut arr, 0, |&(_, n)| n), ("c", 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn select_out_of_range() {
+        select_nth(&mut [1, 2, 3], 3);
+    }
+
+    #[test]
+    fn select_adversarial_comparator() {
+        // same adversary as in the quick sort tests
+        let n = 4096;
+        let gas = n;
+        let mut val = vec![gas; n];
+        let mut solid = 0;
+        let mut candidate = 0;
+        let mut comparisons = 0;
+
+        let mut arr: Vec<usize> = (0..n).collect();
+        select_nth_by(&mut arr, n / 2, |&x, &y| {
+            comparisons += 1;
+            if val[x] == gas && val[y] == gas {
+                if x == candidate {
+                    val[x] = solid;
+                } else {
+                    val[y] = solid;
+                }
+                solid += 1;
+            }
+
+            if val[x] == gas {
+                candidate = x;
+            } else if val[y] == gas {
+                candidate = y;
+            }
+            val[x].cmp(&val[y])
+        });
+
+        assert!(comparisons < 8 * n * n.ilog2() as usize);
+    }
+
+    #[test]
+    fn test_partial_sort() {
+        let input = Distribution::Random.generate(10_000, 2);
+        let mut sorted = input.clone();
+        sorted.sort();
+
+        for k in [0, 1, 10, 500, 9_999, 10_000, 20_000] {
+            let mut arr = input.clone();
+            partial_sort(&mut arr, k);
+            let k = k.min(arr.len());
+            assert_eq!(arr[..k], sorted[..k]);
+
+            arr.sort();
+            assert_eq!(arr, sorted);
+        }
+    }
+
+    #[test]
+    fn partial_sort_descending() {
+        let mut arr = vec![(1, 'a'), (5, 'b'), (3, 'c'), (4, 'd'), (2, 'e')];
+        partial_sort_by_key(&mut arr, 2, |&(n, _)| std::cmp::Reverse(n));
+        assert_eq!(arr[..2], [(5, 'b'), (4, 'd')]);
+
+        partial_sort_by(&mut arr, 3, |a, b| a.cmp(b));
+        assert_eq!(arr[..3], [(1, 'a'), (2, 'e'), (3, 'c')]);
+    }
+
+    #[test]
+    fn top_k_smallest_and_largest() {
+        let input = Distribution::Random.generate(100_000, 3);
+        let mut sorted = input.clone();
+        sorted.sort();
+
+        let smallest: Vec<u64> = input.iter().copied().top_k(10).collect();
+        assert_eq!(smallest, sorted[..10]);
+
+        let largest: Vec<u64> = input
+            .iter()
+            .copied()
+            .top_k_by(10, |a, b| b.cmp(a))
+            .collect();
+        let expected: Vec<u64> = sorted.iter().rev().take(10).copied().collect();
+        assert_eq!(largest, expected);
+    }
+
+    #[test]
+    fn top_k_bounded_memory() {
+        // a long generated stream, never collected
+        let mut rng = Rng::new(0);
+        let stream = std::iter::repeat_with(move || rng.next_u64()).take(200_000);
+
+        let mut top = TopK::new(5);
+        top.extend(stream);
+        assert_eq!(top.len(), 5);
+
+        let sorted = top.into_sorted_vec();
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn top_k_larger_than_input() {
+        let top: Vec<i32> = vec![3, 1, 2].into_iter().top_k(usize::MAX).collect();
+        assert_eq!(top, vec![1, 2, 3]);
+
+        let mut top = TopK::new(usize::MAX);
+        top.push(1);
+        assert!(top.items.capacity() < 1000);
+    }
+
+    #[test]
+    fn top_k_by_key_keeps_first_of_equals() {
+        let words = ["pear", "fig", "kiwi", "plum", "apple", "date", "lime"];
+        let mut shortest: Vec<&str> = words.iter().copied().top_k_by_key(3, |w| w.len()).collect();
+        assert_eq!(shortest[0], "fig");
+        shortest[1..].sort();
+        assert_eq!(shortest[1..], ["kiwi", "pear"]);
+    }
+
+    #[test]
+    fn top_k_edge_cases() {
+        assert_eq!((0..10).top_k(0).count(), 0);
+        assert_eq!((0..3).rev().top_k(10).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(std::iter::empty::<i32>().top_k(3).next(), None);
+    }
+}
@@ -0,0 +1,950 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::ptr;
+
+use super::select::CompareFn;
+
+// The set operations work on multisets the way a merge does: each element of
+// one side cancels out at most one equal element of the other side, so
+// `[1, 1] ∪ [1] == [1, 1]`, `[1, 1] ∩ [1] == [1]` and `[1, 1] - [1] == [1]`.
+
+/// Lazy set operations on sorted iterators, each O(n + m).
+///
+/// Both inputs have to be sorted by the same order the operation is given,
+/// otherwise the output is unspecified (but nothing panics). When an element
+/// of `self` and one of `other` are equal the one from `self` is yielded.
+pub trait SortedIterExt: Iterator + Sized {
+    /// All elements of both, sorted. Stable: ties yield `self` first.
+    fn merge_sorted<J>(self, other: J) -> Merge<Self, J::IntoIter, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+        J: IntoIterator<Item = Self::Item>,
+    {
+        self.merge_sorted_by(other, Self::Item::cmp)
+    }
+
+    fn merge_sorted_by<J, F>(self, other: J, compare: F) -> Merge<Self, J::IntoIter, F>
+    where
+        J: IntoIterator<Item = Self::Item>,
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        Merge(Pair::new(self, other, compare))
+    }
+
+    fn union_sorted<J>(self, other: J) -> Union<Self, J::IntoIter, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+        J: IntoIterator<Item = Self::Item>,
+    {
+        self.union_sorted_by(other, Self::Item::cmp)
+    }
+
+    fn union_sorted_by<J, F>(self, other: J, compare: F) -> Union<Self, J::IntoIter, F>
+    where
+        J: IntoIterator<Item = Self::Item>,
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        Union(Pair::new(self, other, compare))
+    }
+
+    fn intersection_sorted<J>(
+        self,
+        other: J,
+    ) -> Intersection<Self, J::IntoIter, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+        J: IntoIterator<Item = Self::Item>,
+    {
+        self.intersection_sorted_by(other, Self::Item::cmp)
+    }
+
+    fn intersection_sorted_by<J, F>(
+        self,
+        other: J,
+        compare: F,
+    ) -> Intersection<Self, J::IntoIter, F>
+    where
+        J: IntoIterator<Item = Self::Item>,
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        Intersection(Pair::new(self, other, compare))
+    }
+
+    /// Elements of `self` without a match in `other`.
+    fn difference_sorted<J>(self, other: J) -> Difference<Self, J::IntoIter, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+        J: IntoIterator<Item = Self::Item>,
+    {
+        self.difference_sorted_by(other, Self::Item::cmp)
+    }
+
+    fn difference_sorted_by<J, F>(self, other: J, compare: F) -> Difference<Self, J::IntoIter, F>
+    where
+        J: IntoIterator<Item = Self::Item>,
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        Difference(Pair::new(self, other, compare))
+    }
+
+    fn symmetric_difference_sorted<J>(
+        self,
+        other: J,
+    ) -> SymmetricDifference<Self, J::IntoIter, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+        J: IntoIterator<Item = Self::Item>,
+    {
+        self.symmetric_difference_sorted_by(other, Self::Item::cmp)
+    }
+
+    fn symmetric_difference_sorted_by<J, F>(
+        self,
+        other: J,
+        compare: F,
+    ) -> SymmetricDifference<Self, J::IntoIter, F>
+    where
+        J: IntoIterator<Item = Self::Item>,
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        SymmetricDifference(Pair::new(self, other, compare))
+    }
+
+    /// The first element of every run of equal elements.
+    fn dedup_sorted(self) -> Dedup<Self, CompareFn<Self::Item>>
+    where
+        Self::Item: Ord,
+    {
+        self.dedup_sorted_by(Self::Item::cmp)
+    }
+
+    fn dedup_sorted_by<F>(self, compare: F) -> Dedup<Self, F>
+    where
+        F: FnMut(&Self::Item, &Self::Item) -> Ordering,
+    {
+        Dedup {
+            iter: self.peekable(),
+            compare,
+        }
+    }
+}
+
+impl<I: Iterator> SortedIterExt for I {}
+
+/// The same operations on a sorted `Vec`, in place, with a sorted slice as
+/// the other side. The result replaces the contents of the `Vec`, O(n + m),
+/// and keeps the same elements as `SortedIterExt`. The operations that can
+/// add elements grow the `Vec` by at most `other.len()` and clone only the
+/// elements of `other` that end up in it.
+pub trait SortedVecExt<T> {
+    fn merge_sorted(&mut self, other: &[T])
+    where
+        T: Ord + Clone;
+
+    fn merge_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering;
+
+    fn union_sorted(&mut self, other: &[T])
+    where
+        T: Ord + Clone;
+
+    fn union_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering;
+
+    fn intersection_sorted(&mut self, other: &[T])
+    where
+        T: Ord;
+
+    fn intersection_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    fn difference_sorted(&mut self, other: &[T])
+    where
+        T: Ord;
+
+    fn difference_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering;
+
+    fn symmetric_difference_sorted(&mut self, other: &[T])
+    where
+        T: Ord + Clone;
+
+    fn symmetric_difference_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering;
+
+    fn dedup_sorted(&mut self)
+    where
+        T: Ord;
+
+    fn dedup_sorted_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering;
+}
+
+impl<T> SortedVecExt<T> for Vec<T> {
+    fn merge_sorted(&mut self, other: &[T])
+    where
+        T: Ord + Clone,
+    {
+        self.merge_sorted_by(other, T::cmp);
+    }
+
+    fn merge_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        merge_back(self, other, compare, Ties::Both);
+    }
+
+    fn union_sorted(&mut self, other: &[T])
+    where
+        T: Ord + Clone,
+    {
+        self.union_sorted_by(other, T::cmp);
+    }
+
+    fn union_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        merge_back(self, other, compare, Ties::First);
+    }
+
+    fn intersection_sorted(&mut self, other: &[T])
+    where
+        T: Ord,
+    {
+        self.intersection_sorted_by(other, T::cmp);
+    }
+
+    fn intersection_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        retain_matched(self, other, compare, true);
+    }
+
+    fn difference_sorted(&mut self, other: &[T])
+    where
+        T: Ord,
+    {
+        self.difference_sorted_by(other, T::cmp);
+    }
+
+    fn difference_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        retain_matched(self, other, compare, false);
+    }
+
+    fn symmetric_difference_sorted(&mut self, other: &[T])
+    where
+        T: Ord + Clone,
+    {
+        self.symmetric_difference_sorted_by(other, T::cmp);
+    }
+
+    fn symmetric_difference_sorted_by<F>(&mut self, other: &[T], compare: F)
+    where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        merge_back(self, other, compare, Ties::Neither);
+    }
+
+    fn dedup_sorted(&mut self)
+    where
+        T: Ord,
+    {
+        self.dedup_sorted_by(T::cmp);
+    }
+
+    fn dedup_sorted_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.dedup_by(|x, prev| compare(prev, x) == Ordering::Equal);
+    }
+}
+
+// What the in-place operations keep of a group of equal elements, `p` of
+// them from `a` and `q` from `b`. Like the lazy adapters, which pair them up
+// front to back, it is always the last ones of each side that are kept.
+#[derive(Clone, Copy)]
+enum Ties {
+    Both,
+    First,
+    Neither,
+}
+
+impl Ties {
+    fn kept(self, p: usize, q: usize) -> (usize, usize) {
+        let paired = p.min(q);
+        match self {
+            Ties::Both => (p, q),
+            Ties::First => (p, q - paired),
+            Ties::Neither => (p - paired, q - paired),
+        }
+    }
+}
+
+// Merges `b` into `a` from the back. `a` grows by `b.len()` slots of spare
+// capacity and every output element is moved (from `a`) or cloned (from `b`)
+// into the highest free slot. Dropped elements of `a` leave a gap between
+// the unread part and the output, `BackMerge` closes it when it drops.
+fn merge_back<T, F>(a: &mut Vec<T>, b: &[T], mut compare: F, ties: Ties)
+where
+    T: Clone,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = a.len();
+    a.reserve(b.len());
+    let v = a.as_mut_ptr();
+    let mut hole = BackMerge {
+        end: len + b.len(),
+        out: len + b.len(),
+        unread: len,
+        vec: a,
+    };
+    // SAFETY: from here on `hole` tracks what is initialised, `vec[..unread]`
+    // and `vec[out..end]`, and `unread + j <= out` holds in the loop, so a
+    // write to `out - 1` never hits an unread element.
+    unsafe { hole.vec.set_len(0) };
+
+    let mut j = b.len();
+    while j > 0 {
+        let i = hole.unread;
+        let ord = if i == 0 {
+            Ordering::Less
+        } else {
+            compare(unsafe { &*v.add(i - 1) }, &b[j - 1])
+        };
+
+        match ord {
+            Ordering::Greater => unsafe { hole.take(v) },
+            Ordering::Less => {
+                unsafe { hole.push(v, b[j - 1].clone()) };
+                j -= 1;
+            }
+            Ordering::Equal => {
+                let key = &b[j - 1];
+                let mut p = 1;
+                while p < i && compare(unsafe { &*v.add(i - p - 1) }, key) == Ordering::Equal {
+                    p += 1;
+                }
+                let mut q = 1;
+                while q < j && compare(unsafe { &*v.add(i - 1) }, &b[j - q - 1]) == Ordering::Equal
+                {
+                    q += 1;
+                }
+
+                // on ties `b` goes last, which keeps the merge stable
+                let (keep_a, keep_b) = ties.kept(p, q);
+                for n in 0..q {
+                    if n < keep_b {
+                        unsafe { hole.push(v, b[j - 1].clone()) };
+                    }
+                    j -= 1;
+                }
+                for n in 0..p {
+                    if n < keep_a {
+                        unsafe { hole.take(v) };
+                    } else {
+                        unsafe { hole.skip(v) };
+                    }
+                }
+            }
+        }
+    }
+    // the rest of `a` is already in place, dropping `hole` closes the gap
+}
+
+// The state of `merge_back`. On drop, also when `compare` or `clone` panics,
+// the output is moved down onto the unread part and the length restored, so
+// every element is still in the `Vec` exactly once.
+struct BackMerge<'a, T> {
+    vec: &'a mut Vec<T>,
+    unread: usize,
+    out: usize,
+    end: usize,
+}
+
+impl<T> BackMerge<'_, T> {
+    // moves the last unread element of `a` to the output
+    unsafe fn take(&mut self, v: *mut T) {
+        self.unread -= 1;
+        self.out -= 1;
+        ptr::copy(v.add(self.unread), v.add(self.out), 1);
+    }
+
+    // drops the last unread element of `a`
+    unsafe fn skip(&mut self, v: *mut T) {
+        self.unread -= 1;
+        ptr::drop_in_place(v.add(self.unread));
+    }
+
+    unsafe fn push(&mut self, v: *mut T, item: T) {
+        self.out -= 1;
+        ptr::write(v.add(self.out), item);
+    }
+}
+
+impl<T> Drop for BackMerge<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            let v = self.vec.as_mut_ptr();
+            ptr::copy(v.add(self.out), v.add(self.unread), self.end - self.out);
+            self.vec.set_len(self.unread + self.end - self.out);
+        }
+    }
+}
+
+// keeps the elements of `a` that do (or don't) have a match in `b`
+fn retain_matched<T, F>(a: &mut Vec<T>, b: &[T], mut compare: F, matched: bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut b = b.iter().peekable();
+    a.retain(|x| {
+        while b.next_if(|y| compare(y, x) == Ordering::Less).is_some() {}
+        b.next_if(|y| compare(y, x) == Ordering::Equal).is_some() == matched
+    });
+}
+
+enum Next<T> {
+    Left(T),
+    Right(T),
+    Both(T, T),
+}
+
+// the two inputs of a lazy operation, stepped in merge order
+struct Pair<I: Iterator, J: Iterator, F> {
+    a: Peekable<I>,
+    b: Peekable<J>,
+    compare: F,
+}
+
+impl<I, J, F> Pair<I, J, F>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    fn new<B: IntoIterator<IntoIter = J>>(a: I, b: B, compare: F) -> Self {
+        Self {
+            a: a.peekable(),
+            b: b.into_iter().peekable(),
+            compare,
+        }
+    }
+
+    fn ord(&mut self) -> Option<Ordering> {
+        match (self.a.peek(), self.b.peek()) {
+            (Some(x), Some(y)) => Some((self.compare)(x, y)),
+            (Some(_), None) => Some(Ordering::Less),
+            (None, Some(_)) => Some(Ordering::Greater),
+            (None, None) => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Next<I::Item>> {
+        Some(match self.ord()? {
+            Ordering::Less => Next::Left(self.a.next()?),
+            Ordering::Greater => Next::Right(self.b.next()?),
+            Ordering::Equal => Next::Both(self.a.next()?, self.b.next()?),
+        })
+    }
+
+    fn len_bounds(&self) -> (usize, usize, Option<usize>, Option<usize>) {
+        let (a_lo, a_hi) = self.a.size_hint();
+        let (b_lo, b_hi) = self.b.size_hint();
+        (a_lo, b_lo, a_hi, b_hi)
+    }
+}
+
+/// Iterator of `SortedIterExt::merge_sorted`.
+pub struct Merge<I: Iterator, J: Iterator, F>(Pair<I, J, F>);
+
+impl<I, J, F> Iterator for Merge<I, J, F>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        // unlike the set operations, equal elements are yielded one at a time
+        match self.0.ord()? {
+            Ordering::Greater => self.0.b.next(),
+            _ => self.0.a.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_lo, b_lo, a_hi, b_hi) = self.0.len_bounds();
+        let hi = a_hi.zip(b_hi).and_then(|(a, b)| a.checked_add(b));
+        (a_lo.saturating_add(b_lo), hi)
+    }
+}
+
+/// Iterator of `SortedIterExt::union_sorted`.
+pub struct Union<I: Iterator, J: Iterator, F>(Pair<I, J, F>);
+
+impl<I, J, F> Iterator for Union<I, J, F>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        match self.0.next()? {
+            Next::Left(x) | Next::Right(x) | Next::Both(x, _) => Some(x),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_lo, b_lo, a_hi, b_hi) = self.0.len_bounds();
+        let hi = a_hi.zip(b_hi).and_then(|(a, b)| a.checked_add(b));
+        (a_lo.max(b_lo), hi)
+    }
+}
+
+/// Iterator of `SortedIterExt::intersection_sorted`.
+pub struct Intersection<I: Iterator, J: Iterator, F>(Pair<I, J, F>);
+
+impl<I, J, F> Iterator for Intersection<I, J, F>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            match self.0.next()? {
+                Next::Both(x, _) => return Some(x),
+                // nothing left on one side, nothing more can match
+                Next::Left(_) if self.0.b.peek().is_none() => return None,
+                Next::Right(_) if self.0.a.peek().is_none() => return None,
+                _ => {}
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, _, a_hi, b_hi) = self.0.len_bounds();
+        let hi = match (a_hi, b_hi) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        (0, hi)
+    }
+}
+
+/// Iterator of `SortedIterExt::difference_sorted`.
+pub struct Difference<I: Iterator, J: Iterator, F>(Pair<I, J, F>);
+
+impl<I, J, F> Iterator for Difference<I, J, F>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            match self.0.next()? {
+                Next::Left(x) => return Some(x),
+                Next::Right(_) if self.0.a.peek().is_none() => return None,
+                _ => {}
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, _, a_hi, _) = self.0.len_bounds();
+        (0, a_hi)
+    }
+}
+
+/// Iterator of `SortedIterExt::symmetric_difference_sorted`.
+pub struct SymmetricDifference<I: Iterator, J: Iterator, F>(Pair<I, J, F>);
+
+impl<I, J, F> Iterator for SymmetricDifference<I, J, F>
+where
+    I: Iterator,
+    J: Iterator<Item = I::Item>,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            match self.0.next()? {
+                Next::Left(x) | Next::Right(x) => return Some(x),
+                Next::Both(..) => {}
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, _, a_hi, b_hi) = self.0.len_bounds();
+        (0, a_hi.zip(b_hi).and_then(|(a, b)| a.checked_add(b)))
+    }
+}
+
+/// Iterator of `SortedIterExt::dedup_sorted`.
+pub struct Dedup<I: Iterator, F> {
+    iter: Peekable<I>,
+    compare: F,
+}
+
+impl<I, F> Iterator for Dedup<I, F>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let x = self.iter.next()?;
+        let compare = &mut self.compare;
+        while self
+            .iter
+            .next_if(|y| compare(&x, y) == Ordering::Equal)
+            .is_some()
+        {}
+        Some(x)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.iter.size_hint();
+        (lo.min(1), hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Rng;
+
+    fn random_sorted(len: usize, max: u64, rng: &mut Rng) -> Vec<u64> {
+        let mut v: Vec<u64> = (0..len).map(|_| rng.below(max)).collect();
+        v.sort();
+        v
+    }
+
+    // multiset counts of each value, the reference for every operation
+    fn counts(v: &[u64]) -> Vec<usize> {
+        let mut counts = vec![0; 64];
+        for &x in v {
+            counts[x as usize] += 1;
+        }
+        counts
+    }
+
+    fn from_counts(counts: &[usize]) -> Vec<u64> {
+        (0..counts.len())
+            .flat_map(|x| std::iter::repeat_n(x as u64, counts[x]))
+            .collect()
+    }
+
+    fn expected(a: &[u64], b: &[u64], op: Count) -> Vec<u64> {
+        let (ca, cb) = (counts(a), counts(b));
+        let c: Vec<usize> = ca.iter().zip(&cb).map(|(&x, &y)| op(x, y)).collect();
+        from_counts(&c)
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = [1, 3, 5, 7, 9];
+        let b = [3, 4, 5, 6];
+        let it = || (a.iter().copied(), b.iter().copied());
+
+        let (x, y) = it();
+        assert_eq!(
+            x.merge_sorted(y).collect::<Vec<_>>(),
+            vec![1, 3, 3, 4, 5, 5, 6, 7, 9]
+        );
+        let (x, y) = it();
+        assert_eq!(
+            x.union_sorted(y).collect::<Vec<_>>(),
+            vec![1, 3, 4, 5, 6, 7, 9]
+        );
+        let (x, y) = it();
+        assert_eq!(x.intersection_sorted(y).collect::<Vec<_>>(), vec![3, 5]);
+        let (x, y) = it();
+        assert_eq!(x.difference_sorted(y).collect::<Vec<_>>(), vec![1, 7, 9]);
+        let (x, y) = it();
+        assert_eq!(
+            x.symmetric_difference_sorted(y).collect::<Vec<_>>(),
+            vec![1, 4, 6, 7, 9]
+        );
+        assert_eq!(
+            vec![1, 1, 2, 3, 3, 3]
+                .into_iter()
+                .dedup_sorted()
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    type Count = fn(usize, usize) -> usize;
+    type InPlaceI32 = fn(&mut Vec<i32>, &[i32]);
+    type InPlace = fn(&mut Vec<u64>, &[u64]);
+
+    #[test]
+    fn multisets_match_counts() {
+        let mut rng = Rng::new(7);
+        for _ in 0..200 {
+            let (la, lb) = (rng.below(40) as usize, rng.below(40) as usize);
+            let a = random_sorted(la, 8, &mut rng);
+            let b = random_sorted(lb, 8, &mut rng);
+            let it = || (a.iter().copied(), b.iter().copied());
+
+            // (count of a value in the result, lazy result, in-place operation)
+            let cases: [(Count, Vec<u64>, InPlace); 5] = [
+                (
+                    |x, y| x + y,
+                    it().0.merge_sorted(it().1).collect(),
+                    |v, o| v.merge_sorted(o),
+                ),
+                (usize::max, it().0.union_sorted(it().1).collect(), |v, o| {
+                    v.union_sorted(o)
+                }),
+                (
+                    usize::min,
+                    it().0.intersection_sorted(it().1).collect(),
+                    |v, o| v.intersection_sorted(o),
+                ),
+                (
+                    usize::saturating_sub,
+                    it().0.difference_sorted(it().1).collect(),
+                    |v, o| v.difference_sorted(o),
+                ),
+                (
+                    usize::abs_diff,
+                    it().0.symmetric_difference_sorted(it().1).collect(),
+                    |v, o| v.symmetric_difference_sorted(o),
+                ),
+            ];
+
+            for (op, lazy, in_place) in cases {
+                let expected = expected(&a, &b, op);
+                assert_eq!(lazy, expected);
+
+                let mut v = a.clone();
+                in_place(&mut v, &b);
+                assert_eq!(v, expected);
+            }
+
+            let mut v = a.clone();
+            v.dedup_sorted();
+            assert_eq!(v, expected(&a, &a, |x, _| x.min(1)));
+            assert_eq!(a.iter().dedup_sorted().copied().collect::<Vec<_>>(), v);
+        }
+    }
+
+    #[test]
+    fn ties_take_the_left_side() {
+        let a = vec![(1, 'a'), (2, 'a'), (2, 'b')];
+        let b = vec![(2, 'x'), (3, 'x')];
+        let by_key = |x: &(i32, char), y: &(i32, char)| x.0.cmp(&y.0);
+
+        let merged: Vec<_> = a
+            .iter()
+            .copied()
+            .merge_sorted_by(b.iter().copied(), by_key)
+            .collect();
+        assert_eq!(
+            merged,
+            vec![(1, 'a'), (2, 'a'), (2, 'b'), (2, 'x'), (3, 'x')]
+        );
+        let mut v = a.clone();
+        v.merge_sorted_by(&b, by_key);
+        assert_eq!(v, merged);
+
+        let union: Vec<_> = a
+            .iter()
+            .copied()
+            .union_sorted_by(b.iter().copied(), by_key)
+            .collect();
+        assert_eq!(union, vec![(1, 'a'), (2, 'a'), (2, 'b'), (3, 'x')]);
+        let mut v = a.clone();
+        v.union_sorted_by(&b, by_key);
+        assert_eq!(v, union);
+
+        let mut v = a.clone();
+        v.intersection_sorted_by(&b, by_key);
+        assert_eq!(v, vec![(2, 'a')]);
+    }
+
+    #[test]
+    fn in_place_ties_match_lazy() {
+        type Item = (i32, char);
+        type Op = fn(std::vec::IntoIter<Item>, std::vec::IntoIter<Item>) -> Vec<Item>;
+        type InPlace = fn(&mut Vec<Item>, &[Item]);
+        fn by_key(x: &Item, y: &Item) -> Ordering {
+            x.0.cmp(&y.0)
+        }
+
+        let cases: [(Vec<Item>, Vec<Item>); 3] = [
+            (vec![(2, 'a')], vec![(2, 'x'), (2, 'y')]),
+            (vec![(1, 'a'), (1, 'b')], vec![(1, 'x'), (1, 'y'), (1, 'z')]),
+            (
+                vec![(1, 'a'), (2, 'a'), (2, 'b'), (2, 'c'), (4, 'a')],
+                vec![(0, 'x'), (2, 'x'), (3, 'x'), (4, 'x'), (4, 'y')],
+            ),
+        ];
+        let ops: [(Op, InPlace); 3] = [
+            (
+                |a, b| a.merge_sorted_by(b, by_key).collect(),
+                |v, o| v.merge_sorted_by(o, by_key),
+            ),
+            (
+                |a, b| a.union_sorted_by(b, by_key).collect(),
+                |v, o| v.union_sorted_by(o, by_key),
+            ),
+            (
+                |a, b| a.symmetric_difference_sorted_by(b, by_key).collect(),
+                |v, o| v.symmetric_difference_sorted_by(o, by_key),
+            ),
+        ];
+
+        for (a, b) in &cases {
+            for (lazy, in_place) in ops {
+                let expected = lazy(a.clone().into_iter(), b.clone().into_iter());
+                let mut v = a.clone();
+                in_place(&mut v, b);
+                assert_eq!(v, expected);
+            }
+        }
+
+        let mut v = cases[0].0.clone();
+        v.union_sorted_by(&cases[0].1, by_key);
+        assert_eq!(v, vec![(2, 'a'), (2, 'y')]);
+        let mut v = cases[1].0.clone();
+        v.symmetric_difference_sorted_by(&cases[1].1, by_key);
+        assert_eq!(v, vec![(1, 'z')]);
+    }
+
+    #[test]
+    fn in_place_reuses_the_buffer() {
+        let b = vec![2, 3, 5, 8];
+        for op in [
+            <Vec<i32>>::merge_sorted as InPlaceI32,
+            <Vec<i32>>::union_sorted,
+            <Vec<i32>>::symmetric_difference_sorted,
+        ] {
+            let mut v = Vec::with_capacity(16);
+            v.extend([1, 3, 5, 7]);
+            let ptr = v.as_ptr();
+            op(&mut v, &b);
+            assert_eq!(v.as_ptr(), ptr);
+        }
+    }
+
+    #[test]
+    fn in_place_panic_keeps_every_element() {
+        let a: Vec<String> = (0..20).map(|n| format!("{:02}", n * 2)).collect();
+        let b: Vec<String> = (0..20).map(|n| format!("{:02}", n * 3)).collect();
+        let mut v = a.clone();
+        let mut calls = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            v.union_sorted_by(&b, |x, y| {
+                calls += 1;
+                assert!(calls < 15);
+                x.cmp(y)
+            })
+        }));
+        assert!(result.is_err());
+
+        // nothing lost or dropped twice, the clones of `b` may be there too
+        assert!(v.len() >= a.len());
+        for x in &a {
+            assert!(v.contains(x));
+        }
+    }
+
+    #[test]
+    fn descending_comparator() {
+        let a = vec![9, 7, 5, 3];
+        let b = vec![8, 7, 3, 1];
+        let desc = |x: &i32, y: &i32| y.cmp(x);
+        let union: Vec<_> = a
+            .iter()
+            .copied()
+            .union_sorted_by(b.iter().copied(), desc)
+            .collect();
+        assert_eq!(union, vec![9, 8, 7, 5, 3, 1]);
+
+        let mut v = a.clone();
+        v.symmetric_difference_sorted_by(&b, desc);
+        assert_eq!(v, vec![9, 8, 5, 1]);
+    }
+
+    #[test]
+    fn lazy_on_unbounded_input() {
+        let evens = (0..).step_by(2);
+        let threes = (0..).step_by(3);
+        let union: Vec<u64> = evens.clone().union_sorted(threes.clone()).take(8).collect();
+        assert_eq!(union, vec![0, 2, 3, 4, 6, 8, 9, 10]);
+
+        let both: Vec<u64> = evens
+            .clone()
+            .intersection_sorted(threes.clone())
+            .take(3)
+            .collect();
+        assert_eq!(both, vec![0, 6, 12]);
+
+        // stops as soon as one side runs out
+        let finite: Vec<u64> = evens.intersection_sorted(vec![4, 5, 6]).collect();
+        assert_eq!(finite, vec![4, 6]);
+        let finite: Vec<u64> = vec![1, 2, 3]
+            .into_iter()
+            .difference_sorted(threes)
+            .collect();
+        assert_eq!(finite, vec![1, 2]);
+    }
+
+    #[test]
+    fn linear_comparisons() {
+        let a: Vec<u32> = (0..1000).map(|x| x * 2).collect();
+        let b: Vec<u32> = (0..1000).map(|x| x * 3).collect();
+        let mut comparisons = 0;
+        let count = a
+            .iter()
+            .symmetric_difference_sorted_by(b.iter(), |x, y| {
+                comparisons += 1;
+                x.cmp(y)
+            })
+            .count();
+        assert_eq!(count, 1000 + 1000 - 2 * 334);
+        assert!(comparisons <= a.len() + b.len());
+    }
+
+    #[test]
+    fn size_hints() {
+        let merged = (0..3).merge_sorted(5..10);
+        assert_eq!(merged.size_hint(), (8, Some(8)));
+        assert_eq!((0..3).union_sorted(5..10).size_hint(), (5, Some(8)));
+        assert_eq!((0..3).intersection_sorted(5..10).size_hint(), (0, Some(3)));
+        assert_eq!((0..3).difference_sorted(5..10).size_hint(), (0, Some(3)));
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::Sorter;
+
+// Ciura's experimentally found gaps, extended by a factor of 2.25
+const CIURA: [usize; 9] = [1, 4, 10, 23, 57, 132, 301, 701, 1750];
+
+/// Gap sequences for `ShellSort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapSequence<'a> {
+    /// Shell 1959: `n / 2`, `n / 4`, ..., 1. O(n^2) worst case.
+    Shell,
+    /// Knuth 1973: 1, 4, 13, 40, ... `(3^k - 1) / 2`. O(n^(3/2)).
+    Knuth,
+    /// Sedgewick 1986: 1, 8, 23, 77, 281, ... `4^k + 3 * 2^(k-1) + 1`. O(n^(4/3)).
+    Sedgewick,
+    /// Ciura 2001: 1, 4, 10, 23, 57, 132, 301, 701, 1750, then `* 2.25`.
+    Ciura,
+    /// Caller supplied gaps in ascending order. Gaps of zero or not smaller
+    /// than the slice are skipped, and a last pass with gap 1 is added if
+    /// the sequence doesn't start with 1.
+    Custom(&'a [usize]),
+}
+
+impl<'a> GapSequence<'a> {
+    /// The gaps used for a slice of length `len`, largest first.
+    pub fn gaps(self, len: usize) -> Gaps<'a> {
+        let (next, final_one) = match self {
+            _ if len < 2 => (None, false),
+            GapSequence::Custom(gaps) => (gaps.len().checked_sub(1), gaps.first() != Some(&1)),
+            _ => {
+                let mut k = 0;
+                while self.nth(k + 1, len).is_some_and(|gap| gap < len) {
+                    k += 1;
+                }
+                (Some(k), false)
+            }
+        };
+
+        Gaps {
+            sequence: self,
+            len,
+            next,
+            final_one,
+        }
+    }
+
+    /// `k`-th gap in ascending order, `None` past the end of the sequence
+    /// or on overflow.
+    fn nth(self, k: usize, len: usize) -> Option<usize> {
+        let k32 = u32::try_from(k).ok()?;
+        match self {
+            GapSequence::Shell => {
+                let m = len.max(1).ilog2();
+                (k32 < m.max(1)).then(|| (len >> (m - k32)).max(1))
+            }
+            GapSequence::Knuth => 3usize.checked_pow(k32 + 1).map(|p| (p - 1) / 2),
+            GapSequence::Sedgewick => match k32 {
+                0 => Some(1),
+                _ => 4usize
+                    .checked_pow(k32)?
+                    .checked_add(3usize.checked_mul(1usize.checked_shl(k32 - 1)?)?)?
+                    .checked_add(1),
+            },
+            GapSequence::Ciura => match CIURA.get(k) {
+                Some(&gap) => Some(gap),
+                None => {
+                    let mut gap = CIURA[CIURA.len() - 1];
+                    for _ in CIURA.len() - 1..k {
+                        gap = gap.checked_mul(9)? / 4;
+                    }
+                    Some(gap)
+                }
+            },
+            GapSequence::Custom(gaps) => gaps.get(k).copied(),
+        }
+    }
+}
+
+/// Iterator over the gaps of a `GapSequence`, see `GapSequence::gaps`.
+pub struct Gaps<'a> {
+    sequence: GapSequence<'a>,
+    len: usize,
+    next: Option<usize>,
+    final_one: bool,
+}
+
+impl Iterator for Gaps<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while let Some(k) = self.next {
+            self.next = k.checked_sub(1);
+            match self.sequence.nth(k, self.len) {
+                Some(gap) if gap > 0 && gap < self.len => return Some(gap),
+                _ => {}
+            }
+        }
+
+        if self.final_one {
+            self.final_one = false;
+            return Some(1);
+        }
+        None
+    }
+}
+
+/// Shell sort: insertion sort over elements `gap` apart, for shrinking
+/// gaps. In place, no recursion and no allocation. Not stable.
+pub struct ShellSort<'a> {
+    gaps: GapSequence<'a>,
+}
+
+impl<'a> ShellSort<'a> {
+    pub fn new(gaps: GapSequence<'a>) -> Self {
+        Self { gaps }
+    }
+}
+
+impl Default for ShellSort<'_> {
+    fn default() -> Self {
+        Self::new(GapSequence::Ciura)
+    }
+}
+
+impl Sorter for ShellSort<'_> {
+    const STABLE: bool = false;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.sort_by_observed(arr, compare, &mut ());
+    }
+}
+
+impl ObservedSorter for ShellSort<'_> {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        let mut trace = Trace::new(observer);
+        for gap in self.gaps.gaps(arr.len()) {
+            for i in gap..arr.len() {
+                let mut j = i;
+                while j >= gap {
+                    trace.compare(j, j - gap);
+                    if compare(&arr[j], &arr[j - gap]) != Ordering::Less {
+                        break;
+                    }
+                    arr.swap(j, j - gap);
+                    trace.swap(j, j - gap);
+                    j -= gap;
+                }
+            }
+        }
+    }
+}
+
+pub fn shell_sort<T: Ord>(arr: &mut [T]) {
+    ShellSort::default().sort(arr);
+}
+
+pub fn shell_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    ShellSort::default().sort_by(arr, compare);
+}
+
+pub fn shell_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    ShellSort::default().sort_by_key(arr, f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::Distribution;
+
+    const SEQUENCES: [GapSequence; 6] = [
+        GapSequence::Shell,
+        GapSequence::Knuth,
+        GapSequence::Sedgewick,
+        GapSequence::Ciura,
+        GapSequence::Custom(&[1, 3, 7, 15, 31]),
+        GapSequence::Custom(&[5, 0, 2]),
+    ];
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        shell_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn every_sequence() {
+        for len in [0, 1, 2, 3, 10, 100, 1000, 5000] {
+            let input = Distribution::Random.generate(len, len as u64);
+            let mut expected = input.clone();
+            expected.sort();
+
+            for gaps in SEQUENCES {
+                let mut arr = input.clone();
+                ShellSort::new(gaps).sort(&mut arr);
+                assert_eq!(arr, expected, "{:?} len {}", gaps, len);
+            }
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        let gaps = |seq: GapSequence, len| seq.gaps(len).collect::<Vec<_>>();
+
+        assert_eq!(gaps(GapSequence::Shell, 100), vec![50, 25, 12, 6, 3, 1]);
+        assert_eq!(gaps(GapSequence::Knuth, 100), vec![40, 13, 4, 1]);
+        assert_eq!(gaps(GapSequence::Sedgewick, 100), vec![77, 23, 8, 1]);
+        assert_eq!(
+            gaps(GapSequence::Ciura, 10_000),
+            vec![8858, 3937, 1750, 701, 301, 132, 57, 23, 10, 4, 1]
+        );
+        assert_eq!(
+            gaps(GapSequence::Custom(&[1, 4, 9, 200]), 100),
+            vec![9, 4, 1]
+        );
+        assert_eq!(gaps(GapSequence::Custom(&[3, 0, 5]), 100), vec![5, 3, 1]);
+        assert_eq!(gaps(GapSequence::Custom(&[]), 100), vec![1]);
+        assert_eq!(gaps(GapSequence::Ciura, 1), vec![]);
+        assert_eq!(gaps(GapSequence::Shell, 2), vec![1]);
+        assert_eq!(gaps(GapSequence::Knuth, 2), vec![1]);
+    }
+
+    #[test]
+    fn huge_length_does_not_overflow() {
+        for seq in [
+            GapSequence::Knuth,
+            GapSequence::Sedgewick,
+            GapSequence::Ciura,
+        ] {
+            let gaps: Vec<usize> = seq.gaps(usize::MAX).collect();
+            assert_eq!(*gaps.last().unwrap(), 1);
+            assert!(gaps.windows(2).all(|w| w[0] > w[1]));
+        }
+    }
+
+    #[test]
+    fn compare_sequences() {
+        let input = Distribution::Random.generate(5000, 7);
+        let shell = ShellSort::new(GapSequence::Shell).count(&mut input.clone());
+        let ciura = ShellSort::new(GapSequence::Ciura).count(&mut input.clone());
+        assert!(ciura.comparisons < shell.comparisons);
+    }
+
+    #[test]
+    fn by_key_descending() {
+        let mut arr = vec![(1, "a"), (3, "c"), (2, "b"), (4, "d")];
+        shell_sort_by_key(&mut arr, |&(n, _)| std::cmp::Reverse(n));
+        assert_eq!(arr, vec![(4, "d"), (3, "c"), (2, "b"), (1, "a")]);
+
+        shell_sort_by(&mut arr, |a, b| a.1.cmp(b.1));
+        assert_eq!(arr, vec![(1, "a"), (2, "b"), (3, "c"), (4, "d")]);
+    }
+}
@@ -0,0 +1,242 @@
+use std::cmp::Ordering;
+
+use super::{MergeSort, Sorter};
+
+/// Sort direction of one column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+}
+
+/// Where `None` goes in an optional column, independent of the direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+type Column<'a, T> = Box<dyn Fn(&T, &T) -> Ordering + 'a>;
+
+/// Multi-column sort order, built one column at a time.
+///
+/// Rows are compared column by column, the first column that differs
+/// decides. Rows equal in every column keep their order, since the spec only
+/// runs on stable sorters.
+///
+/// ```ignore
+/// let spec = SortSpec::new()
+///     .key(|r: &Row| &r.country, Direction::Ascending)
+///     .option_key(|r| r.revenue.as_ref(), Direction::Descending, Nulls::Last)
+///     .key(|r| r.name.as_str(), Direction::Ascending);
+/// spec.sort(&mut rows);
+/// ```
+pub struct SortSpec<'a, T> {
+    columns: Vec<Column<'a, T>>,
+}
+
+impl<'a, T> SortSpec<'a, T> {
+    pub fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+        }
+    }
+
+    /// Adds a column ordered by the `Ord` of the extracted key.
+    pub fn key<K, F>(self, f: F, direction: Direction) -> Self
+    where
+        K: Ord + ?Sized + 'a,
+        F: Fn(&T) -> &K + 'a,
+    {
+        self.key_by(f, K::cmp, direction)
+    }
+
+    /// Adds a column ordered by `compare` on the extracted key.
+    pub fn key_by<K, F, C>(self, f: F, compare: C, direction: Direction) -> Self
+    where
+        K: ?Sized + 'a,
+        F: Fn(&T) -> &K + 'a,
+        C: Fn(&K, &K) -> Ordering + 'a,
+    {
+        self.by(move |a, b| compare(f(a), f(b)), direction)
+    }
+
+    /// Adds a column with an optional key, `None`s placed by `nulls`.
+    pub fn option_key<K, F>(self, f: F, direction: Direction, nulls: Nulls) -> Self
+    where
+        K: Ord + ?Sized + 'a,
+        F: Fn(&T) -> Option<&K> + 'a,
+    {
+        self.push(move |a, b| match (f(a), f(b)) {
+            (Some(a), Some(b)) => apply(direction, a.cmp(b)),
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) if nulls == Nulls::First => Ordering::Less,
+            (Some(_), None) if nulls == Nulls::Last => Ordering::Less,
+            _ => Ordering::Greater,
+        })
+    }
+
+    /// Adds a column ordered by an arbitrary comparator on whole rows.
+    pub fn by<C>(self, compare: C, direction: Direction) -> Self
+    where
+        C: Fn(&T, &T) -> Ordering + 'a,
+    {
+        self.push(move |a, b| apply(direction, compare(a, b)))
+    }
+
+    fn push<C>(mut self, column: C) -> Self
+    where
+        C: Fn(&T, &T) -> Ordering + 'a,
+    {
+        self.columns.push(Box::new(column));
+        self
+    }
+
+    pub fn compare(&self, a: &T, b: &T) -> Ordering {
+        self.columns
+            .iter()
+            .map(|column| column(a, b))
+            .find(|&ord| ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// Sorts `arr` with `MergeSort`.
+    pub fn sort(&self, arr: &mut [T]) {
+        self.sort_with(&MergeSort, arr);
+    }
+
+    /// Sorts `arr` with `sorter`, which has to be stable.
+    pub fn sort_with<S: Sorter>(&self, sorter: &S, arr: &mut [T]) {
+        const { assert!(S::STABLE, "SortSpec needs a stable sorter") };
+        sorter.sort_by(arr, |a, b| self.compare(a, b));
+    }
+}
+
+impl<T> Default for SortSpec<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn apply(direction: Direction, ord: Ordering) -> Ordering {
+    match direction {
+        Direction::Ascending => ord,
+        Direction::Descending => ord.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{BottomUpMergeSort, TimSort};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Row {
+        country: &'static str,
+        revenue: Option<u32>,
+        name: String,
+    }
+
+    fn row(country: &'static str, revenue: Option<u32>, name: &str) -> Row {
+        Row {
+            country,
+            revenue,
+            name: name.to_string(),
+        }
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            row("kr", Some(10), "b"),
+            row("de", None, "a"),
+            row("kr", Some(30), "c"),
+            row("de", Some(5), "z"),
+            row("kr", None, "a"),
+            row("kr", Some(10), "a"),
+            row("de", Some(5), "y"),
+        ]
+    }
+
+    fn names(rows: &[Row]) -> Vec<&str> {
+        rows.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn multiple_columns() {
+        let spec = SortSpec::new()
+            .key(|r: &Row| r.country, Direction::Ascending)
+            .option_key(|r| r.revenue.as_ref(), Direction::Descending, Nulls::Last)
+            .key(|r| r.name.as_str(), Direction::Ascending);
+
+        let mut arr = rows();
+        spec.sort(&mut arr);
+        assert_eq!(names(&arr), vec!["y", "z", "a", "c", "a", "b", "a"]);
+        assert_eq!(arr[2].revenue, None);
+        assert_eq!(arr[6].revenue, None);
+    }
+
+    #[test]
+    fn nulls_first_ignores_direction() {
+        let mut arr = rows();
+        for direction in [Direction::Ascending, Direction::Descending] {
+            SortSpec::new()
+                .option_key(|r: &Row| r.revenue.as_ref(), direction, Nulls::First)
+                .sort(&mut arr);
+            assert_eq!(arr[0].revenue, None);
+            assert_eq!(arr[1].revenue, None);
+        }
+    }
+
+    #[test]
+    fn ties_keep_order() {
+        let mut arr = rows();
+        SortSpec::new()
+            .key(|r: &Row| r.country, Direction::Descending)
+            .sort(&mut arr);
+        assert_eq!(names(&arr), vec!["b", "c", "a", "a", "a", "z", "y"]);
+    }
+
+    #[test]
+    fn custom_comparators() {
+        let spec = SortSpec::new()
+            .key_by(
+                |r: &Row| r.name.as_str(),
+                |a: &str, b: &str| a.len().cmp(&b.len()),
+                Direction::Descending,
+            )
+            .by(|a, b| a.revenue.cmp(&b.revenue), Direction::Ascending);
+
+        let mut arr = vec![
+            row("kr", Some(2), "aa"),
+            row("kr", None, "b"),
+            row("de", Some(1), "c"),
+        ];
+        spec.sort(&mut arr);
+        assert_eq!(names(&arr), vec!["aa", "b", "c"]);
+    }
+
+    #[test]
+    fn any_stable_sorter() {
+        let spec = SortSpec::new()
+            .key(|r: &Row| r.country, Direction::Ascending)
+            .option_key(|r| r.revenue.as_ref(), Direction::Ascending, Nulls::First);
+
+        let mut expected = rows();
+        spec.sort(&mut expected);
+
+        let mut arr = rows();
+        spec.sort_with(&TimSort, &mut arr);
+        assert_eq!(arr, expected);
+
+        let mut arr = rows();
+        spec.sort_with(&BottomUpMergeSort, &mut arr);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn empty_spec() {
+        let mut arr = rows();
+        SortSpec::new().sort(&mut arr);
+        assert_eq!(arr, rows());
+    }
+}
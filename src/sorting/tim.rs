@@ -0,0 +1,594 @@
+use std::cmp::Ordering;
+use std::ptr;
+use std::slice;
+
+use super::instrument::{ObservedSorter, Observer, Trace};
+use super::merge::MergeHole;
+use super::Sorter;
+
+// consecutive wins of one run before the merge switches to galloping
+const MIN_GALLOP: usize = 7;
+
+/// Adaptive natural merge sort in the style of Timsort. Stable.
+///
+/// The input is split into natural runs (strictly descending runs are
+/// reversed), short runs are extended to `minrun` with binary insertion sort
+/// and the runs are merged from a stack that keeps their lengths balanced.
+/// Merges gallop once one side keeps winning, so already sorted, reversed or
+/// concatenated sorted input takes O(n).
+pub struct TimSort;
+
+impl Sorter for TimSort {
+    const STABLE: bool = true;
+
+    fn sort_by<T, F>(&self, arr: &mut [T], mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        sort(
+            arr,
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(&mut ()),
+        );
+    }
+}
+
+impl ObservedSorter for TimSort {
+    fn sort_by_observed<T, F, O>(&self, arr: &mut [T], mut compare: F, observer: &mut O)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+        O: Observer,
+    {
+        sort(
+            arr,
+            &mut |a, b| compare(a, b) == Ordering::Less,
+            &mut Trace::new(observer),
+        );
+    }
+}
+
+pub fn tim_sort<T: Ord>(arr: &mut [T]) {
+    TimSort.sort(arr);
+}
+
+pub fn tim_sort_by<T, F>(arr: &mut [T], compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    TimSort.sort_by(arr, compare);
+}
+
+pub fn tim_sort_by_key<T, K, F>(arr: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    TimSort.sort_by_key(arr, f);
+}
+
+#[derive(Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+struct State<T> {
+    runs: Vec<Run>,
+    buf: Vec<T>,
+    min_gallop: usize,
+}
+
+fn sort<T, F, O>(v: &mut [T], is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    if len < 2 {
+        return;
+    }
+
+    let min_run = min_run(len);
+    let mut state = State {
+        runs: Vec::new(),
+        buf: Vec::new(),
+        min_gallop: MIN_GALLOP,
+    };
+
+    let mut start = 0;
+    while start < len {
+        let mut run_len = count_run(&mut v[start..], is_less, &mut trace.at(start));
+        if run_len < min_run {
+            let end = (start + min_run).min(len);
+            binary_insertion_sort(&mut v[start..end], run_len, is_less, &mut trace.at(start));
+            run_len = end - start;
+        }
+
+        state.runs.push(Run {
+            start,
+            len: run_len,
+        });
+        start += run_len;
+        merge_collapse(v, &mut state, is_less, trace);
+    }
+
+    while state.runs.len() > 1 {
+        let at = state.runs.len() - 2;
+        merge_at(v, &mut state, at, is_less, trace);
+    }
+}
+
+/// Minimum run length: `n` itself for short input, otherwise a value in
+/// `32..=64` such that `n / min_run` is a power of two or just below one.
+fn min_run(mut n: usize) -> usize {
+    let mut r = 0;
+    while n >= 64 {
+        r |= n & 1;
+        n >>= 1;
+    }
+    n + r
+}
+
+/// Length of the natural run at the start of `v`. A strictly descending run
+/// is reversed in place, strict so that equal elements keep their order.
+fn count_run<T, F, O>(v: &mut [T], is_less: &mut F, trace: &mut Trace<O>) -> usize
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    if len < 2 {
+        return len;
+    }
+
+    trace.compare(1, 0);
+    let descending = is_less(&v[1], &v[0]);
+    let mut end = 2;
+    while end < len {
+        trace.compare(end, end - 1);
+        if is_less(&v[end], &v[end - 1]) != descending {
+            break;
+        }
+        end += 1;
+    }
+
+    if descending {
+        for i in 0..end / 2 {
+            trace.swap(i, end - 1 - i);
+        }
+        v[..end].reverse();
+    }
+    end
+}
+
+/// Sorts `v` given that `v[..sorted]` is already sorted.
+fn binary_insertion_sort<T, F, O>(v: &mut [T], sorted: usize, is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    for i in sorted.max(1)..v.len() {
+        // after all elements equal to the new one, that keeps it stable
+        let pos = gallop(i, |j| {
+            trace.compare(i, j);
+            !is_less(&v[i], &v[j])
+        });
+        if pos < i {
+            v[pos..=i].rotate_right(1);
+            for j in pos..=i {
+                trace.write(j);
+            }
+        }
+    }
+}
+
+/// Merges runs on top of the stack until their lengths satisfy
+/// `A > B + C` and `B > C` (also checked one level deeper, see
+/// "Proving that Android's, Java's and Python's sorting algorithm is broken").
+fn merge_collapse<T, F, O>(v: &mut [T], state: &mut State<T>, is_less: &mut F, trace: &mut Trace<O>)
+where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    loop {
+        let runs = &state.runs;
+        let n = runs.len();
+        if n < 2 {
+            return;
+        }
+
+        let len = |i: usize| runs[i].len;
+        if (n >= 3 && len(n - 3) <= len(n - 2) + len(n - 1))
+            || (n >= 4 && len(n - 4) <= len(n - 3) + len(n - 2))
+        {
+            let at = if len(n - 3) < len(n - 1) {
+                n - 3
+            } else {
+                n - 2
+            };
+            merge_at(v, state, at, is_less, trace);
+        } else if len(n - 2) <= len(n - 1) {
+            merge_at(v, state, n - 2, is_less, trace);
+        } else {
+            return;
+        }
+    }
+}
+
+/// Merges the runs at `at` and `at + 1` of the stack.
+fn merge_at<T, F, O>(
+    v: &mut [T],
+    state: &mut State<T>,
+    at: usize,
+    is_less: &mut F,
+    trace: &mut Trace<O>,
+) where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let a = state.runs[at];
+    let b = state.runs.remove(at + 1);
+    state.runs[at].len += b.len;
+
+    let run = &mut v[a.start..b.start + b.len];
+    let mut trace = trace.at(a.start);
+    let (left, right) = run.split_at(a.len);
+
+    // the start of the left run that is not bigger than the first element
+    // on the right, and the end of the right run that is not smaller than
+    // the last element on the left, are already in place
+    let skip = gallop(a.len, |i| {
+        trace.compare(a.len, i);
+        !is_less(&right[0], &left[i])
+    });
+    if skip == a.len {
+        return;
+    }
+    let keep = gallop(b.len, |j| {
+        trace.compare(a.len + j, a.len - 1);
+        is_less(&right[j], &left[a.len - 1])
+    });
+
+    merge_lo(
+        &mut run[skip..a.len + keep],
+        a.len - skip,
+        &mut state.buf,
+        is_less,
+        &mut state.min_gallop,
+        &mut trace.at(skip),
+    );
+}
+
+/// Length of the prefix of `0..len` for which `pred` holds, found by probing
+/// indices 0, 2, 6, 14, ... and a binary search in the last step. Cheap when
+/// the prefix is short, O(log n) otherwise. `pred` gets indices rather than
+/// elements so the callers can report what they compare.
+fn gallop<P>(len: usize, mut pred: P) -> usize
+where
+    P: FnMut(usize) -> bool,
+{
+    let mut lo = 0;
+    let mut step = 1;
+    while lo + step <= len && pred(lo + step - 1) {
+        lo += step;
+        step *= 2;
+    }
+
+    // the probe at `lo + step - 1` failed, or was past the end
+    let mut hi = (lo + step - 1).min(len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Like `merge::merge`, but switches to galloping when one side wins
+/// `min_gallop` times in a row. `min_gallop` adapts to how well galloping
+/// paid off and is shared by all merges of one sort. Events are reported
+/// like in `merge::merge`: left elements by their position before they were
+/// moved to `buf`, writes only for `v`.
+fn merge_lo<T, F, O>(
+    v: &mut [T],
+    mid: usize,
+    buf: &mut Vec<T>,
+    is_less: &mut F,
+    min_gallop: &mut usize,
+    trace: &mut Trace<O>,
+) where
+    F: FnMut(&T, &T) -> bool,
+    O: Observer,
+{
+    let len = v.len();
+    if mid == 0 || mid >= len {
+        return;
+    }
+
+    debug_assert!(buf.is_empty());
+    buf.reserve(mid);
+
+    // SAFETY: same invariant as `merge::merge`, what is left in `buf` always
+    // fits the hole between `hole.dest` and `right`, and `hole` copies it
+    // back on drop. Blocks from the right run may overlap their destination,
+    // so they are moved with `ptr::copy`.
+    unsafe {
+        let v = v.as_mut_ptr();
+        let b = buf.as_mut_ptr();
+        ptr::copy_nonoverlapping(v, b, mid);
+
+        let mut hole = MergeHole {
+            start: b,
+            end: b.add(mid),
+            dest: v,
+        };
+        let mut right = v.add(mid);
+        let right_end = v.add(len);
+
+        'merge: loop {
+            // one element at a time
+            let mut left_wins = 0;
+            let mut right_wins = 0;
+            while left_wins < *min_gallop && right_wins < *min_gallop {
+                trace.compare(
+                    right.offset_from(v) as usize,
+                    hole.start.offset_from(b) as usize,
+                );
+                trace.write(hole.dest.offset_from(v) as usize);
+                if is_less(&*right, &*hole.start) {
+                    ptr::copy_nonoverlapping(right, hole.dest, 1);
+                    right = right.add(1);
+                    right_wins += 1;
+                    left_wins = 0;
+                } else {
+                    ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                    hole.start = hole.start.add(1);
+                    left_wins += 1;
+                    right_wins = 0;
+                }
+                hole.dest = hole.dest.add(1);
+
+                if hole.start == hole.end || right == right_end {
+                    break 'merge;
+                }
+            }
+
+            // galloping, whole blocks at a time
+            loop {
+                let l = hole.start.offset_from(b) as usize;
+                let r = right.offset_from(v) as usize;
+                let dest = hole.dest.offset_from(v) as usize;
+
+                let left =
+                    slice::from_raw_parts(hole.start, hole.end.offset_from(hole.start) as usize);
+                let k = gallop(left.len(), |i| {
+                    trace.compare(r, l + i);
+                    !is_less(&*right, &left[i])
+                });
+                for i in 0..k {
+                    trace.write(dest + i);
+                }
+                ptr::copy_nonoverlapping(hole.start, hole.dest, k);
+                hole.start = hole.start.add(k);
+                hole.dest = hole.dest.add(k);
+                if hole.start == hole.end {
+                    break 'merge;
+                }
+
+                let rest = slice::from_raw_parts(right, right_end.offset_from(right) as usize);
+                let j = gallop(rest.len(), |i| {
+                    trace.compare(r + i, l + k);
+                    is_less(&rest[i], &*hole.start)
+                });
+                for i in 0..j {
+                    trace.write(dest + k + i);
+                }
+                ptr::copy(right, hole.dest, j);
+                right = right.add(j);
+                hole.dest = hole.dest.add(j);
+                if right == right_end {
+                    break 'merge;
+                }
+
+                if k < MIN_GALLOP && j < MIN_GALLOP {
+                    *min_gallop += 1;
+                    break;
+                }
+                *min_gallop = min_gallop.saturating_sub(1).max(1);
+            }
+        }
+
+        // whatever is left in `buf` is copied back by `hole` when it drops
+        let dest = hole.dest.offset_from(v) as usize;
+        for i in 0..hole.end.offset_from(hole.start) as usize {
+            trace.write(dest + i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorting::{Distribution, ObservedSorter};
+
+    fn comparisons(arr: &mut [u64]) -> usize {
+        let mut count = 0;
+        tim_sort_by(arr, |a, b| {
+            count += 1;
+            a.cmp(b)
+        });
+        count
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut arr = vec![6, 5, 3, 1, 8, 7, 2, 4];
+        tim_sort(&mut arr);
+        assert_eq!(arr, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let mut arr: Vec<i32> = vec![];
+        tim_sort(&mut arr);
+        assert!(arr.is_empty());
+
+        let mut arr = vec![1];
+        tim_sort(&mut arr);
+        assert_eq!(arr, vec![1]);
+    }
+
+    #[test]
+    fn random_input() {
+        for len in [2, 31, 64, 65, 100, 1000, 10_000, 100_000] {
+            let mut arr = Distribution::Random.generate(len, len as u64);
+            let mut expected = arr.clone();
+            expected.sort();
+
+            tim_sort(&mut arr);
+            assert_eq!(arr, expected);
+        }
+    }
+
+    #[test]
+    fn stable_with_duplicate_keys() {
+        let records: Vec<(u64, usize)> = Distribution::Random
+            .generate(20_000, 7)
+            .iter()
+            .map(|k| k % 10)
+            .zip(0..)
+            .collect();
+        let mut expected = records.clone();
+        expected.sort_by_key(|&(k, _)| k);
+
+        let mut arr = records.clone();
+        tim_sort_by_key(&mut arr, |&(k, _)| k);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn sorted_is_linear() {
+        let n = 100_000;
+        let mut arr: Vec<u64> = (0..n).collect();
+        assert_eq!(comparisons(&mut arr), n as usize - 1);
+        assert_eq!(arr, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reversed_is_linear() {
+        let n = 100_000;
+        let mut arr: Vec<u64> = (0..n).rev().collect();
+        assert_eq!(comparisons(&mut arr), n as usize - 1);
+        assert_eq!(arr, (0..n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reversed_with_duplicates_is_stable() {
+        // strictly descending runs only, equal neighbours must not be swapped
+        let mut arr: Vec<(u64, usize)> = (0..1000).map(|i| ((1000 - i) / 3, i as usize)).collect();
+        let mut expected = arr.clone();
+        expected.sort_by_key(|&(k, _)| k);
+
+        tim_sort_by_key(&mut arr, |&(k, _)| k);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn concatenated_runs() {
+        let n = 100_000;
+        let runs = 8;
+        let mut arr: Vec<u64> = (0..runs)
+            .flat_map(|r| (0..n / runs).map(move |i| i * runs + r))
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let count = comparisons(&mut arr);
+        assert_eq!(arr, expected);
+        // about one pass to find the runs plus log2(8) = 3 levels of merging,
+        // a plain merge sort needs n * log2(n), roughly 17 * n here
+        assert!(count < 5 * n as usize, "{} comparisons", count);
+    }
+
+    #[test]
+    fn interleaving_blocks_gallop() {
+        let n = 100_000;
+        let mut arr: Vec<u64> = (0..n / 2).chain(n / 2..n).rev().collect();
+        arr[..n as usize / 2].reverse();
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let count = comparisons(&mut arr);
+        assert_eq!(arr, expected);
+        assert!(count < 2 * n as usize, "{} comparisons", count);
+    }
+
+    #[test]
+    fn observed_runs() {
+        let n = 10_000;
+        let mut arr: Vec<u64> = (0..n).collect();
+        let counters = TimSort.count(&mut arr);
+        assert_eq!(counters.comparisons, n as usize - 1);
+        assert_eq!(counters.writes, 0);
+
+        // a descending run is reversed with n / 2 swaps and nothing else
+        let mut arr: Vec<u64> = (0..n).rev().collect();
+        let counters = TimSort.count(&mut arr);
+        assert_eq!(arr, (0..n).collect::<Vec<_>>());
+        assert_eq!(counters.swaps, n as usize / 2);
+        assert_eq!(counters.writes, n as usize);
+
+        // every element of a merge is written once
+        let mut arr: Vec<u64> = (n / 2..n).chain(0..n / 2).collect();
+        let counters = TimSort.count(&mut arr);
+        assert_eq!(arr, (0..n).collect::<Vec<_>>());
+        assert_eq!(counters.writes, n as usize);
+    }
+
+    #[test]
+    fn min_run_length() {
+        assert_eq!(min_run(10), 10);
+        assert_eq!(min_run(63), 63);
+        assert_eq!(min_run(64), 32);
+        assert_eq!(min_run(65), 33);
+        assert_eq!(min_run(2048), 32);
+        assert_eq!(min_run(2049), 33);
+    }
+
+    #[test]
+    fn gallop_prefix() {
+        let v: Vec<usize> = (0..100).collect();
+        for k in 0..=100 {
+            assert_eq!(gallop(v.len(), |i| v[i] < k), k);
+        }
+    }
+
+    #[test]
+    fn panic_in_compare_keeps_elements() {
+        let mut arr: Vec<String> = Distribution::Random
+            .generate(2000, 3)
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let mut calls = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            tim_sort_by(&mut arr, |a, b| {
+                calls += 1;
+                if calls == 15_000 {
+                    panic!("compare");
+                }
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err());
+
+        arr.sort();
+        assert_eq!(arr, expected);
+    }
+}